@@ -0,0 +1,105 @@
+use std::{env, thread, time};
+use embedded_hal::digital::v2::OutputPin;
+use spidev::{Spidev, SpidevOptions, SpiModeFlags};
+use sysfs_gpio::{Direction, Pin};
+use st7789v::ST7789V;
+use st7789v::image_convert::to_rgb565;
+
+
+// versionFive Gpio
+pub const GPIOCHIP_BASE: u8 = 0;
+pub const LCD_CS: u8 = GPIOCHIP_BASE + 49;
+pub const LCD_RST: u8 = GPIOCHIP_BASE + 42;
+pub const LCD_DC: u8 = GPIOCHIP_BASE + 44;
+pub const LCD_BL: u8 = GPIOCHIP_BASE + 51;
+// versionFive Gpio
+
+
+struct MyPin(Pin);
+
+impl OutputPin for MyPin {
+    type Error = ();
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.0.set_value(0).unwrap();
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.0.set_value(1).unwrap();
+        Ok(())
+    }
+}
+
+struct Delay;
+
+impl embedded_hal::blocking::delay::DelayMs<u16> for Delay {
+    fn delay_ms(&mut self, ms: u16) {
+        let millis = time::Duration::from_millis(ms as u64);
+        thread::sleep(millis);
+    }
+}
+
+fn output_pin(number: u8) -> MyPin {
+    let pin = Pin::new(number as u64);
+    pin.export().expect("[init_dev] error ");
+    pin.set_direction(Direction::Out).expect("[init_dev] error ");
+    MyPin(pin)
+}
+
+pub struct HardwareSpi {
+    pub spi: Spidev
+}
+
+impl HardwareSpi {
+    pub fn new(device_name: &str) -> Self {
+        let mut spi = Spidev::open(device_name).unwrap_or_else(|_| panic!("open {} error", device_name));
+        let options = SpidevOptions::new()
+            .bits_per_word(8)
+            .max_speed_hz(10000000)
+            .mode(SpiModeFlags::SPI_MODE_0)
+            .build();
+        spi.configure(&options).unwrap_or_else(|_| panic!("spi configure {} error", device_name));
+        HardwareSpi {
+            spi
+        }
+    }
+}
+
+impl embedded_hal::blocking::spi::Write<u8> for HardwareSpi {
+    type Error = ();
+
+    fn write(&mut self, words: &[u8]) -> Result<(), Self::Error> {
+        std::io::Write::write_all(&mut self.spi, words).map_err(|_| ())
+    }
+}
+
+
+// cargo run --example image --features image -- path/to/picture.png
+fn main() {
+    let path = env::args().nth(1).expect("usage: image <picture.png|jpg|bmp>");
+
+    let width = 240;   // short side
+    let height = 320;  // long side
+
+    // decode and scale down so the picture fits the panel
+    let img = image::open(&path).expect("[open image] error");
+    let img = img.thumbnail(width as u32, height as u32);
+    let data = to_rgb565(&img);
+
+    let cs = output_pin(LCD_CS);
+    let dc = output_pin(LCD_DC);
+    let rst = output_pin(LCD_RST);
+    let mut bl = output_pin(LCD_BL);
+    bl.set_high().expect("[backlight] error");
+
+    let device = HardwareSpi::new("/dev/spidev1.0");
+    let mut display = ST7789V::with_cs(device, cs, dc, rst, width, height).expect("Init display error!");
+    display.init(&mut Delay).expect("Init delay error!");
+
+    display
+        .write_raw_image(0, 0, img.width() as u16, img.height() as u16, &data)
+        .expect("[write_raw_image] error");
+
+    display.release().expect("[release display] error");
+}
@@ -0,0 +1,100 @@
+use std::cell::RefCell;
+use std::{thread, time};
+use embedded_hal::digital::v2::OutputPin;
+use spidev::{Spidev, SpidevOptions, SpiModeFlags};
+use sysfs_gpio::{Direction, Pin};
+use st7789v::{SharedSpi, ST7789V};
+
+
+// versionFive Gpio, both panels share SPI, DC, RST and BL, only CS differs
+pub const GPIOCHIP_BASE: u8 = 0;
+pub const LCD_CS: u8 = GPIOCHIP_BASE + 49;
+pub const LCD2_CS: u8 = GPIOCHIP_BASE + 50;
+pub const LCD_RST: u8 = GPIOCHIP_BASE + 42;
+pub const LCD_DC: u8 = GPIOCHIP_BASE + 44;
+pub const LCD_BL: u8 = GPIOCHIP_BASE + 51;
+// versionFive Gpio
+
+
+struct MyPin(Pin);
+
+impl OutputPin for MyPin {
+    type Error = ();
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.0.set_value(0).unwrap();
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.0.set_value(1).unwrap();
+        Ok(())
+    }
+}
+
+struct Delay;
+
+impl embedded_hal::blocking::delay::DelayMs<u16> for Delay {
+    fn delay_ms(&mut self, ms: u16) {
+        let millis = time::Duration::from_millis(ms as u64);
+        thread::sleep(millis);
+    }
+}
+
+fn output_pin(number: u8) -> MyPin {
+    let pin = Pin::new(number as u64);
+    pin.export().expect("[init_dev] error ");
+    pin.set_direction(Direction::Out).expect("[init_dev] error ");
+    MyPin(pin)
+}
+
+pub struct HardwareSpi {
+    pub spi: Spidev
+}
+
+impl HardwareSpi {
+    pub fn new(device_name: &str) -> Self {
+        let mut spi = Spidev::open(device_name).unwrap_or_else(|_| panic!("open {} error", device_name));
+        let options = SpidevOptions::new()
+            .bits_per_word(8)
+            .max_speed_hz(10000000)
+            .mode(SpiModeFlags::SPI_MODE_0)
+            .build();
+        spi.configure(&options).unwrap_or_else(|_| panic!("spi configure {} error", device_name));
+        HardwareSpi {
+            spi
+        }
+    }
+}
+
+impl embedded_hal::blocking::spi::Write<u8> for HardwareSpi {
+    type Error = ();
+
+    fn write(&mut self, words: &[u8]) -> Result<(), Self::Error> {
+        std::io::Write::write_all(&mut self.spi, words).map_err(|_| ())
+    }
+}
+
+
+// cargo run --example two_displays
+fn main() {
+    let bus = RefCell::new(HardwareSpi::new("/dev/spidev1.0"));
+    let mut delay = Delay;
+
+    // sysfs lets both drivers hold a handle to the shared DC and RST lines
+    let mut left = ST7789V::with_cs(SharedSpi::new(&bus), output_pin(LCD_CS), output_pin(LCD_DC), output_pin(LCD_RST), 240, 320)
+        .expect("Init display error!");
+    let mut right = ST7789V::with_cs(SharedSpi::new(&bus), output_pin(LCD2_CS), output_pin(LCD_DC), output_pin(LCD_RST), 240, 320)
+        .expect("Init display error!");
+
+    // one reset pulse resets both panels, so only the first init pulses RST
+    left.init(&mut delay).expect("[init] error");
+    right.init_no_reset(&mut delay).expect("[init_no_reset] error");
+    output_pin(LCD_BL).set_high().expect("[backlight] error");
+
+    left.fill_screen(0xF800).expect("[fill_screen] error");
+    right.fill_screen(0x001F).expect("[fill_screen] error");
+
+    left.release().expect("[release display] error");
+    right.release().expect("[release display] error");
+}
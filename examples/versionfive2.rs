@@ -1,20 +1,18 @@
 use std::io::Write;
 use std::{thread, time};
-use embedded_graphics::drawable::Drawable;
-use embedded_graphics::{DrawTarget, text_style};
+use embedded_graphics::DrawTarget;
 use embedded_graphics::fonts::{Font8x16, Text};
 use embedded_graphics::geometry::Point;
-use embedded_graphics::image::{Image, ImageRaw, ImageRawBE, ImageRawLE};
-use embedded_graphics::pixelcolor::{BinaryColor, Rgb565, Rgb888, RgbColor};
-use embedded_graphics::prelude::{Dimensions, Pixel, Primitive, Size};
+use embedded_graphics::image::{Image, ImageRawLE};
+use embedded_graphics::pixelcolor::{Rgb565, RgbColor};
+use embedded_graphics::prelude::Primitive;
 use embedded_graphics::primitives::{Line, Circle, Rectangle};
 use embedded_graphics::style::{PrimitiveStyle, TextStyle};
 use st7789v::{ST7789V};
 use embedded_hal::digital::v2::OutputPin;
-use embedded_hal::prelude::_embedded_hal_blocking_spi_Transfer;
 use spidev::{Spidev, SpidevOptions, SpiModeFlags};
 use sysfs_gpio::{Direction, Pin};
-use st7789v::Rotate::{Rotate0, Rotate180, Rotate270, Rotate90};
+use st7789v::Rotate::Rotate90;
 
 
 // versionFive Gpio
@@ -94,13 +92,13 @@ pub struct HardwareSpi{
 impl HardwareSpi {
     // new HardwareSpi instance
     pub fn new(device_name: &str) -> Self {
-        let mut spi = Spidev::open(device_name).expect(format!("open {} error", device_name).as_str());
+        let mut spi = Spidev::open(device_name).unwrap_or_else(|_| panic!("open {} error", device_name));
         let options = SpidevOptions::new()
             .bits_per_word(8)
             .max_speed_hz(10000000)
             .mode(SpiModeFlags::SPI_MODE_0)
             .build();
-        spi.configure(&options).expect(format!("spi configure {} error", device_name).as_str());
+        spi.configure(&options).unwrap_or_else(|_| panic!("spi configure {} error", device_name));
         HardwareSpi{
             spi
         }
@@ -111,7 +109,7 @@ impl embedded_hal::blocking::spi::Write<u8> for HardwareSpi {
     type Error = ();
 
     fn write(&mut self, words: &[u8]) -> Result<(), Self::Error> {
-        self.spi.write(words).expect("1111");
+        self.spi.write_all(words).expect("1111");
         Ok(())
     }
 }
@@ -164,10 +162,10 @@ fn main() {
     let style = TextStyle::new(Font8x16, Rgb565::BLUE);
 
     let text = Text::new("hello world", Point::new(10, 100)).into_styled(style);
-    display.draw_iter(text.into_iter());
+    display.draw_iter(&text).expect("[draw_text] error");
 
     let rect = Rectangle::new(Point::new(0, 0), Point::new(50, 100));
-    display.draw_rectangle(&rect.into_styled(PrimitiveStyle::with_fill(Rgb565::RED)));
+    display.draw_rectangle(&rect.into_styled(PrimitiveStyle::with_fill(Rgb565::RED))).expect("[draw_rectangle] error");
     // release
     display.release().expect("[release display] error");
     // backlight
@@ -0,0 +1,271 @@
+//! Draw into a frame buffer in RAM and send it to the panel in one go.
+//!
+//! `BufferedDisplay` implements `DrawTarget` on a `Vec<u16>` the size of the panel and
+//! remembers the rectangle touched since the last flush. `flush` sends just that
+//! rectangle, so a UI redraws without the flicker of drawing on the glass directly.
+//!
+//! Without a heap, `from_buffer` takes any fixed storage instead, a `&'static mut [u16]`,
+//! an array or a `heapless::Vec`; the streaming paths of the driver use small buffers on
+//! the stack and need no allocation at all.
+
+use embedded_graphics::drawable::Pixel;
+use embedded_graphics::pixelcolor::raw::{RawData, RawU16};
+use embedded_graphics::pixelcolor::Rgb565;
+use embedded_graphics::prelude::{DrawTarget, Size};
+
+use embedded_hal::blocking::spi;
+use embedded_hal::digital::v2::OutputPin;
+
+use crate::{Error, ST7789V};
+
+/// `ST7789V` with a RAM frame buffer, see the module docs
+pub struct BufferedDisplay<SPI, CS, DC, RST, PinError, SpiError, B = Vec<u16>>
+where
+    SPI: spi::Write<u8>,
+    CS: OutputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+{
+    display: ST7789V<SPI, CS, DC, RST, PinError, SpiError>,
+    buf: B,
+    /// Touched area since the last flush as `(xs, ys, xe, ye)`, ends exclusive
+    dirty: Option<(u16, u16, u16, u16)>,
+}
+
+impl<SPI, CS, DC, RST, PinError, SpiError> BufferedDisplay<SPI, CS, DC, RST, PinError, SpiError>
+where
+    SPI: spi::Write<u8, Error = SpiError>,
+    CS: OutputPin<Error = PinError>,
+    DC: OutputPin<Error = PinError>,
+    RST: OutputPin<Error = PinError>,
+{
+    /// Wrap an initialized `display`, the buffer starts out black (and not dirty).
+    pub fn new(display: ST7789V<SPI, CS, DC, RST, PinError, SpiError>) -> Self {
+        let (width, height) = display.native_size();
+        BufferedDisplay {
+            display,
+            buf: vec![0; width as usize * height as usize],
+            dirty: None,
+        }
+    }
+}
+
+impl<SPI, CS, DC, RST, PinError, SpiError, B>
+    BufferedDisplay<SPI, CS, DC, RST, PinError, SpiError, B>
+where
+    SPI: spi::Write<u8, Error = SpiError>,
+    CS: OutputPin<Error = PinError>,
+    DC: OutputPin<Error = PinError>,
+    RST: OutputPin<Error = PinError>,
+    B: AsRef<[u16]> + AsMut<[u16]>,
+{
+    /// Wrap an initialized `display` around `buf`, which needs at least width x height
+    /// entries (`InvalidBufferSize` otherwise). The contents are kept and not dirty, use
+    /// `flush_all` to send them.
+    pub fn from_buffer(
+        display: ST7789V<SPI, CS, DC, RST, PinError, SpiError>,
+        buf: B,
+    ) -> Result<Self, Error<PinError, SpiError>> {
+        let (width, height) = display.native_size();
+        if buf.as_ref().len() < width as usize * height as usize {
+            return Err(Error::InvalidBufferSize);
+        }
+
+        Ok(BufferedDisplay {
+            display,
+            buf,
+            dirty: None,
+        })
+    }
+
+    /// Send the area drawn on since the last flush, nothing if there is none.
+    pub fn flush(&mut self) -> Result<(), Error<PinError, SpiError>> {
+        let (xs, ys, xe, ye) = match self.dirty.take() {
+            Some(dirty) => dirty,
+            None => return Ok(()),
+        };
+        let width = self.display.native_size().0 as usize;
+        let buf = self.buf.as_ref();
+        let mut colors = (ys as usize..ye as usize).flat_map(|y| {
+            buf[y * width + xs as usize..y * width + xe as usize]
+                .iter()
+                .copied()
+        });
+        self.display.pixels(xs, ys, xe, ye, &mut colors)?;
+
+        Ok(())
+    }
+
+    /// Send the whole buffer, e.g. after the panel was reset.
+    pub fn flush_all(&mut self) -> Result<(), Error<PinError, SpiError>> {
+        let (width, height) = self.display.native_size();
+        self.dirty = Some((0, 0, width, height));
+        self.flush()
+    }
+
+    /// The frame buffer, row by row
+    pub fn buffer(&self) -> &[u16] {
+        self.buf.as_ref()
+    }
+
+    /// The wrapped driver, for register calls. Drawing through it bypasses the buffer.
+    pub fn display(&mut self) -> &mut ST7789V<SPI, CS, DC, RST, PinError, SpiError> {
+        &mut self.display
+    }
+
+    /// Give the driver back, dropping the buffer
+    pub fn release(self) -> ST7789V<SPI, CS, DC, RST, PinError, SpiError> {
+        self.display
+    }
+
+    /// Give the driver and the buffer back
+    pub fn into_parts(self) -> (ST7789V<SPI, CS, DC, RST, PinError, SpiError>, B) {
+        (self.display, self.buf)
+    }
+}
+
+impl<SPI, CS, DC, RST, PinError, SpiError, B> DrawTarget<Rgb565>
+    for BufferedDisplay<SPI, CS, DC, RST, PinError, SpiError, B>
+where
+    SPI: spi::Write<u8, Error = SpiError>,
+    CS: OutputPin<Error = PinError>,
+    DC: OutputPin<Error = PinError>,
+    RST: OutputPin<Error = PinError>,
+    B: AsRef<[u16]> + AsMut<[u16]>,
+{
+    type Error = Error<PinError, SpiError>;
+
+    fn draw_pixel(&mut self, pixel: Pixel<Rgb565>) -> Result<(), Self::Error> {
+        let (width, height) = self.display.native_size();
+        let (x, y) = (pixel.0.x, pixel.0.y);
+        if x < 0 || y < 0 || x >= width as i32 || y >= height as i32 {
+            return Ok(());
+        }
+        let (x, y) = (x as u16, y as u16);
+        self.buf.as_mut()[y as usize * width as usize + x as usize] =
+            RawU16::from(pixel.1).into_inner();
+        self.dirty = Some(match self.dirty {
+            Some((xs, ys, xe, ye)) => (xs.min(x), ys.min(y), xe.max(x + 1), ye.max(y + 1)),
+            None => (x, y, x + 1, y + 1),
+        });
+
+        Ok(())
+    }
+
+    fn clear(&mut self, color: Rgb565) -> Result<(), Self::Error> {
+        let (width, height) = self.display.native_size();
+        let len = width as usize * height as usize;
+        for slot in &mut self.buf.as_mut()[..len] {
+            *slot = RawU16::from(color).into_inner();
+        }
+        self.dirty = Some((0, 0, width, height));
+
+        Ok(())
+    }
+
+    fn size(&self) -> Size {
+        let (width, height) = self.display.native_size();
+        Size::new(width as u32, height as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_graphics::pixelcolor::{Rgb565, RgbColor};
+    use embedded_graphics::prelude::*;
+    use embedded_graphics::primitives::Rectangle;
+    use embedded_graphics::style::PrimitiveStyle;
+
+    use super::BufferedDisplay;
+    use crate::command::Command;
+    use crate::mock::display;
+    use crate::Error;
+
+    #[test]
+    fn flush_sends_dirty_rect_once() {
+        let (display, _, log) = display(240, 320);
+        let mut buffered = BufferedDisplay::new(display);
+        Rectangle::new(Point::new(10, 20), Point::new(12, 21))
+            .into_styled(PrimitiveStyle::with_fill(Rgb565::RED))
+            .draw(&mut buffered)
+            .unwrap();
+        Pixel(Point::new(400, 5), Rgb565::RED)
+            .draw(&mut buffered)
+            .unwrap();
+        assert!(log.commands().is_empty());
+
+        buffered.flush().unwrap();
+        let commands = log.commands();
+        assert_eq!(commands[0], (Command::CASET.value(), vec![0, 10, 0, 12]));
+        assert_eq!(commands[1], (Command::RASET.value(), vec![0, 20, 0, 21]));
+        assert_eq!(commands[2].1.len(), 3 * 2 * 2);
+
+        log.clear();
+        buffered.flush().unwrap();
+        assert!(log.commands().is_empty());
+    }
+
+    #[test]
+    fn clear_marks_whole_buffer() {
+        let (display, _, log) = display(4, 2);
+        let mut buffered = BufferedDisplay::new(display);
+        buffered.clear(Rgb565::BLUE).unwrap();
+        assert!(buffered.buffer().iter().all(|&c| c == 0x001F));
+        assert!(log.commands().is_empty());
+
+        buffered.flush().unwrap();
+        let commands = log.commands();
+        assert_eq!(commands.len(), 3);
+        assert_eq!(commands[2].1.len(), 4 * 2 * 2);
+    }
+
+    #[test]
+    fn flush_all_sends_whole_buffer() {
+        let (display, _, log) = display(4, 2);
+        let mut buf = [0u16; 8];
+        for (i, c) in buf.iter_mut().enumerate() {
+            *c = i as u16;
+        }
+        let mut buffered = BufferedDisplay::from_buffer(display, &mut buf[..]).unwrap();
+        Pixel(Point::new(1, 0), Rgb565::WHITE)
+            .draw(&mut buffered)
+            .unwrap();
+
+        // the whole panel, clean or not, and nothing left for the next flush
+        buffered.flush_all().unwrap();
+        let commands = log.commands();
+        assert_eq!(commands[0], (Command::CASET.value(), vec![0, 0, 0, 3]));
+        assert_eq!(commands[1], (Command::RASET.value(), vec![0, 0, 0, 1]));
+        assert_eq!(
+            commands[2].1,
+            [0, 0, 0xFF, 0xFF, 0, 2, 0, 3, 0, 4, 0, 5, 0, 6, 0, 7]
+        );
+
+        log.clear();
+        buffered.flush().unwrap();
+        assert!(log.commands().is_empty());
+        buffered.flush_all().unwrap();
+        assert_eq!(log.commands().len(), 3);
+    }
+
+    #[test]
+    fn fixed_buffer() {
+        let (display, _, log) = display(4, 2);
+        let mut buf = [0x1234u16; 8];
+        let mut buffered = BufferedDisplay::from_buffer(display, &mut buf[..]).unwrap();
+        Pixel(Point::new(3, 1), Rgb565::WHITE)
+            .draw(&mut buffered)
+            .unwrap();
+        buffered.flush().unwrap();
+        assert_eq!(log.commands()[2].1, [0xFF, 0xFF]);
+
+        let (display, buf) = buffered.into_parts();
+        assert_eq!(buf[7], 0xFFFF);
+        assert_eq!(buf[0], 0x1234);
+        let mut short = [0u16; 7];
+        assert!(matches!(
+            BufferedDisplay::from_buffer(display, &mut short[..]),
+            Err(Error::InvalidBufferSize)
+        ));
+    }
+}
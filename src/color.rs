@@ -0,0 +1,21 @@
+//! Color conversions that need no embedded-graphics, for colors from config files or
+//! assets.
+
+/// 24 bit RGB888 to RGB565, keeping the top bits of each channel.
+pub const fn rgb888_to_rgb565(r: u8, g: u8, b: u8) -> u16 {
+    ((r as u16 & 0xF8) << 8) | ((g as u16 & 0xFC) << 3) | (b as u16 >> 3)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::rgb888_to_rgb565;
+
+    #[test]
+    fn rgb888() {
+        const ORANGE: u16 = rgb888_to_rgb565(0xFF, 0x80, 0x00);
+        assert_eq!(ORANGE, 0xFC00);
+        assert_eq!(rgb888_to_rgb565(0xFF, 0xFF, 0xFF), 0xFFFF);
+        assert_eq!(rgb888_to_rgb565(0x07, 0x03, 0x07), 0x0000);
+        assert_eq!(rgb888_to_rgb565(0x00, 0x00, 0xFF), 0x001F);
+    }
+}
@@ -1,5 +1,6 @@
 /// LCD Command
-#[allow(unused, non_camel_case_types)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[allow(unused, non_camel_case_types, clippy::upper_case_acronyms)]
 #[repr(u8)]
 pub enum Command {
     /// No operation
@@ -34,6 +35,10 @@ pub enum Command {
     INVOFF = 0x20,
     /// Display inversion on
     INVON = 0x21,
+    /// All pixels off (MIPI DCS, not in the ST7789V datasheet)
+    ALLPOFF = 0x22,
+    /// All pixels on (MIPI DCS, not in the ST7789V datasheet)
+    ALLPON = 0x23,
     /// Display inversion on (gamma?)
     GAMSET = 0x26,
     /// Display off
@@ -92,10 +97,16 @@ pub enum Command {
     RDCABCMB = 0x5F,
     /// Read Automatic brightness control self-diagnostic result
     RDABCSDR = 0x68,
+    /// RAM control
+    RAMCTRL = 0xB0,
+    /// RGB interface control
+    RGBCTRL = 0xB1,
     /// Porch setting
     PORCTRL = 0xB2,
     /// Gate control
     GCTRL = 0xB7,
+    /// Digital gamma enable
+    DGMEN = 0xBA,
     /// VCOMS setting
     VCOMS = 0xBB,
     /// LCM control
@@ -119,14 +130,40 @@ pub enum Command {
     RDID2 = 0xDB,
     /// Read ID3
     RDID3 = 0xDC,
+    /// Command 2 enable
+    CMD2EN = 0xDF,
 
+    /// Positive voltage gamma control
     E0 = 0xE0,
-    E1 = 0xE1
+    /// Negative voltage gamma control
+    E1 = 0xE1,
+    /// Digital gamma look-up table for red
+    DGMLUTR = 0xE2,
+    /// Digital gamma look-up table for blue
+    DGMLUTB = 0xE3,
+    /// Gate control
+    GATECTRL = 0xE4,
+    /// Inter register enable 2 (clone panels, not in the ST7789V datasheet)
+    INREGEN2 = 0xEF,
+    /// NVM setting, address and data to program
+    NVMSET = 0xFC,
+    /// Inter register enable 1 on clone panels, the same byte is PROMACT on a genuine
+    /// ST7789V
+    INREGEN1 = 0xFE,
 }
 
 impl Command {
+    /// Program action, starts the NVM write set up by NVMSET (shares 0xFE with `INREGEN1`)
+    pub const PROMACT: Command = Command::INREGEN1;
+
     /// Get command as value.
     pub fn value(self) -> u8 {
         self as u8
     }
 }
+
+impl From<Command> for u8 {
+    fn from(cmd: Command) -> u8 {
+        cmd.value()
+    }
+}
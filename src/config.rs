@@ -0,0 +1,172 @@
+//! Snapshot of the register configuration, for suspend and resume.
+//!
+//! A panel that loses power forgets every register. `export_config` captures what the
+//! driver has set, `apply_config` writes it all back after power returns, so custom
+//! tuning (gamma, voltages, porches) survives without being set up again by hand.
+
+use embedded_hal::blocking::delay::DelayMs;
+use embedded_hal::blocking::spi;
+use embedded_hal::digital::v2::OutputPin;
+
+use crate::command::Command;
+use crate::{Error, Rotate, ST7789V};
+
+/// Register configuration of a display, see `ST7789V::export_config`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DisplayConfig {
+    /// Rotation set with `set_rotate`
+    pub rotation: Rotate,
+    /// MADCTL value
+    pub madctl: u8,
+    /// COLMOD value
+    pub colmod: u8,
+    /// INVON (true) or INVOFF
+    pub inverted: bool,
+    /// Positive voltage gamma table (E0)
+    pub gamma_positive: [u8; 14],
+    /// Negative voltage gamma table (E1)
+    pub gamma_negative: [u8; 14],
+    /// PORCTRL parameters
+    pub porch: [u8; 5],
+    /// VCOMS value
+    pub vcom: u8,
+    /// VRHS value
+    pub vrh: u8,
+    /// VDVS value
+    pub vdv: u8,
+    /// FRCTRL2 value
+    pub frctrl2: u8,
+    /// WRCTRLD value
+    pub ctrl_display: u8,
+    /// WRCACE value
+    pub cabc: u8,
+}
+
+impl<SPI, CS, DC, RST, PinError, SpiError> ST7789V<SPI, CS, DC, RST, PinError, SpiError>
+where
+    SPI: spi::Write<u8, Error = SpiError>,
+    CS: OutputPin<Error = PinError>,
+    DC: OutputPin<Error = PinError>,
+    RST: OutputPin<Error = PinError>,
+{
+    /// The register configuration as last set through the driver.
+    pub fn export_config(&self) -> DisplayConfig {
+        DisplayConfig {
+            rotation: self.rotate,
+            madctl: self.madctl,
+            colmod: self.colmod,
+            inverted: self.invert,
+            gamma_positive: self.gamma_positive,
+            gamma_negative: self.gamma_negative,
+            porch: self.porch,
+            vcom: self.vcom,
+            vrh: self.vrh,
+            vdv: self.vdv,
+            frctrl2: self.frctrl2,
+            ctrl_display: self.ctrl_display,
+            cabc: self.cabc,
+        }
+    }
+
+    /// Take over `config` and write it to the panel with the full init sequence (sleep
+    /// out and display on included), then WRCTRLD and WRCACE.
+    ///
+    /// Meant for resuming after the panel lost power, where it comes up reset. Frame
+    /// memory is not part of the configuration, redraw afterwards.
+    pub fn apply_config<DELAY>(
+        &mut self,
+        config: &DisplayConfig,
+        delay: &mut DELAY,
+    ) -> Result<&mut Self, Error<PinError, SpiError>>
+    where
+        DELAY: DelayMs<u16>,
+    {
+        self.rotate = config.rotation;
+        self.madctl = config.madctl;
+        self.colmod = config.colmod;
+        self.invert = config.inverted;
+        self.gamma_positive = config.gamma_positive;
+        self.gamma_negative = config.gamma_negative;
+        self.porch = config.porch;
+        self.vcom = config.vcom;
+        self.vrh = config.vrh;
+        self.vdv = config.vdv;
+        self.frctrl2 = config.frctrl2;
+        self.init_no_reset(delay)?;
+
+        self.ctrl_display = config.ctrl_display;
+        self.command(Command::WRCTRLD, Some(&[config.ctrl_display]))?
+            .write_cabc(config.cabc)
+    }
+
+    /// Recover from a glitch that cost the panel its registers but not its power: software
+    /// reset, then `apply_config` with the current configuration.
+    ///
+    /// Skips the reset pulse of `init`, so it works with RST shared or not wired. Frame
+    /// memory survives SWRESET, the picture comes back as it was.
+    pub fn recover<DELAY>(
+        &mut self,
+        delay: &mut DELAY,
+    ) -> Result<&mut Self, Error<PinError, SpiError>>
+    where
+        DELAY: DelayMs<u16>,
+    {
+        let config = self.export_config();
+        self.soft_reset(delay)?;
+        self.apply_config(&config, delay)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::command::Command;
+    use crate::mock::{display, Event};
+    use crate::{CabcMode, Rotate};
+
+    #[test]
+    fn recover_without_reset_pulse() {
+        let (mut display, mut delay, log) = display(240, 320);
+        display.set_vcom(0x22).unwrap();
+        log.clear();
+        display.recover(&mut delay).unwrap();
+
+        assert!(!log.events().iter().any(|e| matches!(e, Event::Rst(_))));
+        let commands = log.commands();
+        assert_eq!(commands[0].0, Command::SWRESET.value());
+        assert!(commands.contains(&(Command::VCOMS.value(), vec![0x22])));
+        assert!(commands.contains(&(Command::DISPON.value(), vec![])));
+        assert!(display.state().awake);
+    }
+
+    #[test]
+    fn config_survives_power_loss() {
+        let (mut old, mut delay, _) = display(240, 320);
+        let gamma = [0x11; 14];
+        old.set_gamma(&gamma, &gamma).unwrap();
+        old.set_vcom(0x20).unwrap();
+        old.set_cabc(CabcMode::StillPicture).unwrap();
+        old.set_rotate(Rotate::Rotate90).unwrap();
+        old.invert_on_init(false);
+        let config = old.export_config();
+
+        let (mut fresh, _, log) = display(240, 320);
+        fresh.apply_config(&config, &mut delay).unwrap();
+        assert_eq!(fresh.export_config(), config);
+
+        let commands = log.commands();
+        let sent = |cmd: Command| {
+            commands
+                .iter()
+                .find(|c| c.0 == cmd.value())
+                .map(|c| c.1.clone())
+        };
+        assert_eq!(sent(Command::E0), Some(gamma.to_vec()));
+        assert_eq!(sent(Command::E1), Some(gamma.to_vec()));
+        assert_eq!(sent(Command::VCOMS), Some(vec![0x20]));
+        assert!(sent(Command::INVOFF).is_some());
+        assert_eq!(
+            commands.last().unwrap(),
+            &(Command::WRCACE.value(), vec![config.cabc])
+        );
+    }
+}
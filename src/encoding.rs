@@ -0,0 +1,128 @@
+//! How RGB565 colors go out on the bus in each control interface color format.
+//!
+//! Every format implements `PixelEncoding`. The driver picks the implementation that
+//! matches COLMOD once per write, so the drawing methods keep taking RGB565 whatever
+//! the panel is set to, and a new format only needs a type here and a COLMOD arm in
+//! `ST7789V::encoder`.
+
+use crate::rgb565_to_rgb444;
+
+/// Encoding of RGB565 colors for one color format
+pub trait PixelEncoding {
+    /// Pixels encoded together, more than one where pixels share bytes
+    const PIXELS: usize;
+    /// Bytes a group of `PIXELS` pixels takes, at most 3
+    const BYTES: usize;
+
+    /// Encode 1 to `PIXELS` colors into `buf`, returning the number of bytes used.
+    /// Fewer colors than `PIXELS` only come at the end of a write.
+    fn encode(colors: &[u16], buf: &mut [u8]) -> usize;
+
+    /// Bytes `pixels` pixels take on the bus
+    fn data_len(pixels: usize) -> usize {
+        pixels.div_ceil(Self::PIXELS) * Self::BYTES
+    }
+}
+
+/// 16 bit RGB565, two big endian bytes per pixel (COLMOD 0b101)
+pub struct Bits16;
+
+impl PixelEncoding for Bits16 {
+    const PIXELS: usize = 1;
+    const BYTES: usize = 2;
+
+    fn encode(colors: &[u16], buf: &mut [u8]) -> usize {
+        buf[..2].copy_from_slice(&colors[0].to_be_bytes());
+        2
+    }
+}
+
+/// 12 bit RGB444, two pixels packed into three bytes (COLMOD 0b011). An odd pixel at
+/// the end goes out as two bytes with the low half of the second one unused.
+pub struct Bits12;
+
+impl PixelEncoding for Bits12 {
+    const PIXELS: usize = 2;
+    const BYTES: usize = 3;
+
+    fn encode(colors: &[u16], buf: &mut [u8]) -> usize {
+        let a = rgb565_to_rgb444(colors[0]);
+        match colors.get(1).copied().map(rgb565_to_rgb444) {
+            Some(b) => {
+                buf[..3].copy_from_slice(&[
+                    (a >> 4) as u8,
+                    ((a & 0xF) << 4 | b >> 8) as u8,
+                    b as u8,
+                ]);
+                3
+            }
+            None => {
+                buf[..2].copy_from_slice(&[(a >> 4) as u8, ((a & 0xF) << 4) as u8]);
+                2
+            }
+        }
+    }
+
+    fn data_len(pixels: usize) -> usize {
+        (pixels * 3).div_ceil(2)
+    }
+}
+
+/// 18 bit RGB666, one byte per channel in D7..D2 (COLMOD 0b110, and the truncated
+/// 0b111). Red and blue repeat their top bit as the sixth, so white stays full white.
+pub struct Bits18;
+
+impl PixelEncoding for Bits18 {
+    const PIXELS: usize = 1;
+    const BYTES: usize = 3;
+
+    fn encode(colors: &[u16], buf: &mut [u8]) -> usize {
+        let c = colors[0];
+        let (r, g, b) = ((c >> 11) as u8, (c >> 5) as u8 & 0x3F, c as u8 & 0x1F);
+        let six = |v: u8| (v << 3) | ((v >> 4) << 2);
+        buf[..3].copy_from_slice(&[six(r), g << 2, six(b)]);
+        3
+    }
+}
+
+/// A `PixelEncoding` picked at run time
+#[derive(Clone, Copy)]
+pub(crate) struct Encoder {
+    pub(crate) pixels: usize,
+    pub(crate) encode: fn(&[u16], &mut [u8]) -> usize,
+    pub(crate) data_len: fn(usize) -> usize,
+}
+
+impl Encoder {
+    pub(crate) fn of<E: PixelEncoding>() -> Self {
+        Encoder {
+            pixels: E::PIXELS,
+            encode: E::encode,
+            data_len: E::data_len,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Bits12, Bits16, Bits18, PixelEncoding};
+
+    #[test]
+    fn formats() {
+        let mut buf = [0u8; 3];
+        assert_eq!(Bits16::encode(&[0xF81F], &mut buf), 2);
+        assert_eq!(buf[..2], [0xF8, 0x1F]);
+
+        assert_eq!(Bits18::encode(&[0xFFFF], &mut buf), 3);
+        assert_eq!(buf, [0xFC, 0xFC, 0xFC]);
+        Bits18::encode(&[0x07E0], &mut buf);
+        assert_eq!(buf, [0x00, 0xFC, 0x00]);
+
+        assert_eq!(Bits12::encode(&[0xF800, 0x001F], &mut buf), 3);
+        assert_eq!(buf, [0xF0, 0x00, 0x0F]);
+        assert_eq!(Bits12::encode(&[0xFFFF], &mut buf), 2);
+        assert_eq!(buf[..2], [0xFF, 0xF0]);
+        assert_eq!(Bits12::data_len(3), 5);
+        assert_eq!(Bits18::data_len(3), 9);
+    }
+}
@@ -0,0 +1,160 @@
+//! Solid fills over buses that can send 16 bit words.
+//!
+//! With the SPI peripheral in 16 bit frame mode, one word per pixel goes out instead of
+//! two bytes, which halves the per pixel work for large solid areas. The methods only
+//! exist when the bus implements `Write<u16>` as well as `Write<u8>`.
+
+use embedded_hal::blocking::spi;
+use embedded_hal::digital::v2::OutputPin;
+
+use crate::{Error, ST7789V};
+
+impl<SPI, CS, DC, RST, PinError, SpiError> ST7789V<SPI, CS, DC, RST, PinError, SpiError>
+where
+    SPI: spi::Write<u8, Error = SpiError> + spi::Write<u16, Error = SpiError>,
+    CS: OutputPin<Error = PinError>,
+    DC: OutputPin<Error = PinError>,
+    RST: OutputPin<Error = PinError>,
+{
+    /// Fill the window `xs`..`xe`, `ys`..`ye` (ends exclusive) with `color`, sent as 16
+    /// bit words.
+    ///
+    /// The bus has to clock words out MSB first, the usual setting, so the panel sees the
+    /// same big endian bytes as from the byte path. In the 12 and 18 bit modes pixels do not
+    /// line up with words, so the fill goes out through the byte path instead. Same
+    /// bounds checks as `pixels`, and the words get the same retries as any other write
+    /// (`set_write_retries`).
+    pub fn fast_fill(
+        &mut self,
+        xs: u16,
+        ys: u16,
+        xe: u16,
+        ye: u16,
+        color: u16,
+    ) -> Result<&mut Self, Error<PinError, SpiError>> {
+        if xs.max(xe) > self.width {
+            return Err(Error::InvalidColumnAddress);
+        }
+        if ys.max(ye) > self.height {
+            return Err(Error::InvalidRowAddress);
+        }
+        self.address_window(xs, ys, xe, ye)?;
+
+        let mut left = xe.saturating_sub(xs) as usize * ye.saturating_sub(ys) as usize;
+        if !self.rgb565_on_bus() {
+            self.write_solid(color, left)?;
+            return Ok(self);
+        }
+        let buf = [self.corrected(color); 256];
+        self.select()?;
+        self.cfg.dc.set_high().map_err(Error::Pin)?;
+        while left > 0 {
+            let n = left.min(buf.len());
+            self.data16(&buf[..n])?;
+            left -= n;
+        }
+        self.deselect()?;
+
+        Ok(self)
+    }
+
+    /// `fast_fill` over the whole panel.
+    pub fn fast_fill_screen(&mut self, color: u16) -> Result<&mut Self, Error<PinError, SpiError>> {
+        let (width, height) = self.native_size();
+        self.fast_fill(0, 0, width, height, color)
+    }
+
+    /// `data` for 16 bit words, retried like `spi_write`
+    fn data16(&mut self, words: &[u16]) -> Result<(), Error<PinError, SpiError>> {
+        #[cfg(feature = "log")]
+        log::trace!("st7789v: data, {} words", words.len());
+
+        let mut retries = self.write_retries;
+        loop {
+            match spi::Write::<u16>::write(&mut self.spi, words) {
+                Ok(()) => return Ok(()),
+                Err(_) if retries > 0 => retries -= 1,
+                Err(e) => return Err(Error::Spi(e)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use crate::command::Command;
+    use crate::mock::{display, Event, FlakyWords, Log, MockPin, PinKind};
+    use crate::{Error, ST7789V};
+
+    fn words(log: &Log) -> Vec<u16> {
+        log.events()
+            .into_iter()
+            .filter_map(|e| match e {
+                Event::Write16(words) => Some(words),
+                _ => None,
+            })
+            .flatten()
+            .collect()
+    }
+
+    #[test]
+    fn fill_in_words() {
+        let (mut display, _, log) = display(240, 320);
+        display.fast_fill(10, 10, 30, 30, 0xF800).unwrap();
+
+        let commands = log.commands();
+        assert_eq!(commands[0], (Command::CASET.value(), vec![0, 10, 0, 29]));
+        assert_eq!(commands[2], (Command::RAMWR.value(), vec![]));
+        let words = words(&log);
+        assert_eq!(words.len(), 20 * 20);
+        assert!(words.iter().all(|&w| w == 0xF800));
+    }
+
+    #[test]
+    fn fill_screen_in_words() {
+        let (mut display, _, log) = display(240, 320);
+        display.fast_fill_screen(0x001F).unwrap();
+
+        let commands = log.commands();
+        assert_eq!(commands[0], (Command::CASET.value(), vec![0, 0, 0, 239]));
+        assert_eq!(commands[1], (Command::RASET.value(), vec![0, 0, 0x01, 0x3F]));
+        let words = words(&log);
+        assert_eq!(words.len(), 240 * 320);
+        assert!(words.iter().all(|&w| w == 0x001F));
+        assert_eq!(log.events().last(), Some(&Event::Cs(true)));
+    }
+
+    #[test]
+    fn fill_bounds() {
+        let (mut display, _, log) = display(240, 320);
+
+        let wide = display.fast_fill(230, 0, 250, 10, 0);
+        assert!(matches!(wide, Err(Error::InvalidColumnAddress)));
+        let tall = display.fast_fill(0, 310, 10, 330, 0);
+        assert!(matches!(tall, Err(Error::InvalidRowAddress)));
+        assert!(log.events().is_empty());
+    }
+
+    #[test]
+    fn words_retried() {
+        let log = Log::default();
+        let failures = Rc::new(Cell::new(1));
+        let mut display: ST7789V<_, MockPin, _, _, _, _> = ST7789V::new(
+            FlakyWords(log.clone(), failures.clone()),
+            MockPin(log.clone(), PinKind::Dc),
+            MockPin(log.clone(), PinKind::Rst),
+            240,
+            320,
+        );
+        assert!(matches!(display.fast_fill(0, 0, 2, 2, 0xFFFF), Err(Error::Spi(()))));
+
+        log.clear();
+        failures.set(1);
+        display.set_write_retries(1);
+        display.fast_fill(0, 0, 2, 2, 0xFFFF).unwrap();
+        assert_eq!(words(&log), [0xFFFF; 4]);
+    }
+}
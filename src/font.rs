@@ -0,0 +1,239 @@
+//! Built-in 5x7 ASCII font, for status text without pulling in embedded-graphics.
+
+use embedded_hal::blocking::spi;
+use embedded_hal::digital::v2::OutputPin;
+
+use crate::{Error, ST7789V};
+
+/// Width of a character cell in pixels at scale 1, 5 columns of glyph plus 1 of spacing
+pub const CHAR_WIDTH: u16 = 6;
+/// Height of a character cell in pixels at scale 1, 7 rows of glyph plus 1 of spacing
+pub const CHAR_HEIGHT: u16 = 8;
+/// Largest scale `draw_char` accepts, larger values are clamped
+pub const MAX_SCALE: u8 = 8;
+
+/// Glyphs for ' ' (0x20) to '~' (0x7E), five columns each with the top row in bit 0
+const FONT_5X7: [[u8; 5]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00], // ' '
+    [0x00, 0x00, 0x5F, 0x00, 0x00], // !
+    [0x00, 0x07, 0x00, 0x07, 0x00], // "
+    [0x14, 0x7F, 0x14, 0x7F, 0x14], // #
+    [0x24, 0x2A, 0x7F, 0x2A, 0x12], // $
+    [0x23, 0x13, 0x08, 0x64, 0x62], // %
+    [0x36, 0x49, 0x55, 0x22, 0x50], // &
+    [0x00, 0x05, 0x03, 0x00, 0x00], // '
+    [0x00, 0x1C, 0x22, 0x41, 0x00], // (
+    [0x00, 0x41, 0x22, 0x1C, 0x00], // )
+    [0x08, 0x2A, 0x1C, 0x2A, 0x08], // *
+    [0x08, 0x08, 0x3E, 0x08, 0x08], // +
+    [0x00, 0x50, 0x30, 0x00, 0x00], // ,
+    [0x08, 0x08, 0x08, 0x08, 0x08], // -
+    [0x00, 0x60, 0x60, 0x00, 0x00], // .
+    [0x20, 0x10, 0x08, 0x04, 0x02], // /
+    [0x3E, 0x51, 0x49, 0x45, 0x3E], // 0
+    [0x00, 0x42, 0x7F, 0x40, 0x00], // 1
+    [0x42, 0x61, 0x51, 0x49, 0x46], // 2
+    [0x21, 0x41, 0x45, 0x4B, 0x31], // 3
+    [0x18, 0x14, 0x12, 0x7F, 0x10], // 4
+    [0x27, 0x45, 0x45, 0x45, 0x39], // 5
+    [0x3C, 0x4A, 0x49, 0x49, 0x30], // 6
+    [0x01, 0x71, 0x09, 0x05, 0x03], // 7
+    [0x36, 0x49, 0x49, 0x49, 0x36], // 8
+    [0x06, 0x49, 0x49, 0x29, 0x1E], // 9
+    [0x00, 0x36, 0x36, 0x00, 0x00], // :
+    [0x00, 0x56, 0x36, 0x00, 0x00], // ;
+    [0x08, 0x14, 0x22, 0x41, 0x00], // <
+    [0x14, 0x14, 0x14, 0x14, 0x14], // =
+    [0x00, 0x41, 0x22, 0x14, 0x08], // >
+    [0x02, 0x01, 0x51, 0x09, 0x06], // ?
+    [0x32, 0x49, 0x79, 0x41, 0x3E], // @
+    [0x7E, 0x11, 0x11, 0x11, 0x7E], // A
+    [0x7F, 0x49, 0x49, 0x49, 0x36], // B
+    [0x3E, 0x41, 0x41, 0x41, 0x22], // C
+    [0x7F, 0x41, 0x41, 0x22, 0x1C], // D
+    [0x7F, 0x49, 0x49, 0x49, 0x41], // E
+    [0x7F, 0x09, 0x09, 0x01, 0x01], // F
+    [0x3E, 0x41, 0x41, 0x51, 0x32], // G
+    [0x7F, 0x08, 0x08, 0x08, 0x7F], // H
+    [0x00, 0x41, 0x7F, 0x41, 0x00], // I
+    [0x20, 0x40, 0x41, 0x3F, 0x01], // J
+    [0x7F, 0x08, 0x14, 0x22, 0x41], // K
+    [0x7F, 0x40, 0x40, 0x40, 0x40], // L
+    [0x7F, 0x02, 0x04, 0x02, 0x7F], // M
+    [0x7F, 0x04, 0x08, 0x10, 0x7F], // N
+    [0x3E, 0x41, 0x41, 0x41, 0x3E], // O
+    [0x7F, 0x09, 0x09, 0x09, 0x06], // P
+    [0x3E, 0x41, 0x51, 0x21, 0x5E], // Q
+    [0x7F, 0x09, 0x19, 0x29, 0x46], // R
+    [0x46, 0x49, 0x49, 0x49, 0x31], // S
+    [0x01, 0x01, 0x7F, 0x01, 0x01], // T
+    [0x3F, 0x40, 0x40, 0x40, 0x3F], // U
+    [0x1F, 0x20, 0x40, 0x20, 0x1F], // V
+    [0x7F, 0x20, 0x18, 0x20, 0x7F], // W
+    [0x63, 0x14, 0x08, 0x14, 0x63], // X
+    [0x03, 0x04, 0x78, 0x04, 0x03], // Y
+    [0x61, 0x51, 0x49, 0x45, 0x43], // Z
+    [0x00, 0x7F, 0x41, 0x41, 0x00], // [
+    [0x02, 0x04, 0x08, 0x10, 0x20], // backslash
+    [0x00, 0x41, 0x41, 0x7F, 0x00], // ]
+    [0x04, 0x02, 0x01, 0x02, 0x04], // ^
+    [0x40, 0x40, 0x40, 0x40, 0x40], // _
+    [0x00, 0x01, 0x02, 0x04, 0x00], // `
+    [0x20, 0x54, 0x54, 0x54, 0x78], // a
+    [0x7F, 0x48, 0x44, 0x44, 0x38], // b
+    [0x38, 0x44, 0x44, 0x44, 0x20], // c
+    [0x38, 0x44, 0x44, 0x48, 0x7F], // d
+    [0x38, 0x54, 0x54, 0x54, 0x18], // e
+    [0x08, 0x7E, 0x09, 0x01, 0x02], // f
+    [0x0C, 0x52, 0x52, 0x52, 0x3E], // g
+    [0x7F, 0x08, 0x04, 0x04, 0x78], // h
+    [0x00, 0x44, 0x7D, 0x40, 0x00], // i
+    [0x20, 0x40, 0x44, 0x3D, 0x00], // j
+    [0x7F, 0x10, 0x28, 0x44, 0x00], // k
+    [0x00, 0x41, 0x7F, 0x40, 0x00], // l
+    [0x7C, 0x04, 0x18, 0x04, 0x78], // m
+    [0x7C, 0x08, 0x04, 0x04, 0x78], // n
+    [0x38, 0x44, 0x44, 0x44, 0x38], // o
+    [0x7C, 0x14, 0x14, 0x14, 0x08], // p
+    [0x08, 0x14, 0x14, 0x18, 0x7C], // q
+    [0x7C, 0x08, 0x04, 0x04, 0x08], // r
+    [0x48, 0x54, 0x54, 0x54, 0x20], // s
+    [0x04, 0x3F, 0x44, 0x40, 0x20], // t
+    [0x3C, 0x40, 0x40, 0x20, 0x7C], // u
+    [0x1C, 0x20, 0x40, 0x20, 0x1C], // v
+    [0x3C, 0x40, 0x30, 0x40, 0x3C], // w
+    [0x44, 0x28, 0x10, 0x28, 0x44], // x
+    [0x0C, 0x50, 0x50, 0x50, 0x3C], // y
+    [0x44, 0x64, 0x54, 0x4C, 0x44], // z
+    [0x00, 0x08, 0x36, 0x41, 0x00], // {
+    [0x00, 0x00, 0x7F, 0x00, 0x00], // |
+    [0x00, 0x41, 0x36, 0x08, 0x00], // }
+    [0x08, 0x04, 0x08, 0x10, 0x08], // ~
+];
+
+/// Glyph columns for `c`, '?' for anything outside printable ASCII
+fn glyph(c: char) -> &'static [u8; 5] {
+    let index = match c {
+        ' '..='~' => c as usize - ' ' as usize,
+        _ => '?' as usize - ' ' as usize,
+    };
+    &FONT_5X7[index]
+}
+
+impl<SPI, CS, DC, RST, PinError, SpiError> ST7789V<SPI, CS, DC, RST, PinError, SpiError>
+where
+    SPI: spi::Write<u8, Error = SpiError>,
+    CS: OutputPin<Error = PinError>,
+    DC: OutputPin<Error = PinError>,
+    RST: OutputPin<Error = PinError>,
+{
+    /// Draws `c` in a `CHAR_WIDTH` x `CHAR_HEIGHT` cell, every font pixel `scale` pixels
+    /// wide and high, with its top left corner at `x`, `y`.
+    ///
+    /// The whole cell is written, glyph pixels in `fg` and the rest in `bg`, so text can be
+    /// redrawn in place without clearing first. Characters outside printable ASCII are
+    /// drawn as '?'. `scale` is clamped to 1 - `MAX_SCALE`.
+    pub fn draw_char(
+        &mut self,
+        x: u16,
+        y: u16,
+        c: char,
+        fg: u16,
+        bg: u16,
+        scale: u8,
+    ) -> Result<&mut Self, Error<PinError, SpiError>> {
+        const STRIDE: usize = (CHAR_WIDTH as usize * MAX_SCALE as usize).div_ceil(8);
+
+        let scale = scale.clamp(1, MAX_SCALE) as usize;
+        let (w, h) = (CHAR_WIDTH as usize * scale, CHAR_HEIGHT as usize * scale);
+        let columns = glyph(c);
+
+        // scaled 1 bit bitmap of the cell, MSB first as draw_mono_bitmap wants it
+        let stride = w.div_ceil(8);
+        let mut bits = [0u8; STRIDE * CHAR_HEIGHT as usize * MAX_SCALE as usize];
+        for row in 0..h {
+            for col in 0..w {
+                let set = columns
+                    .get(col / scale)
+                    .is_some_and(|column| column & (1 << (row / scale)) != 0);
+                if set {
+                    bits[row * stride + col / 8] |= 0x80 >> (col % 8);
+                }
+            }
+        }
+
+        self.draw_mono_bitmap(x, y, w as u16, h as u16, &bits[..stride * h], fg, bg)
+    }
+
+    /// Draws `text` left to right starting at `x`, `y`, see `draw_char`.
+    ///
+    /// '\n' starts a new line below at `x`. Nothing wraps or clips at the panel edge, so
+    /// keep lines short enough to fit (`CHAR_WIDTH * scale` pixels per character).
+    pub fn draw_text(
+        &mut self,
+        x: u16,
+        y: u16,
+        text: &str,
+        fg: u16,
+        bg: u16,
+        scale: u8,
+    ) -> Result<&mut Self, Error<PinError, SpiError>> {
+        let scale = scale.clamp(1, MAX_SCALE) as u16;
+        let (mut cx, mut cy) = (x, y);
+        for c in text.chars() {
+            if c == '\n' {
+                cx = x;
+                cy = cy.saturating_add(CHAR_HEIGHT * scale);
+                continue;
+            }
+            self.draw_char(cx, cy, c, fg, bg, scale as u8)?;
+            cx = cx.saturating_add(CHAR_WIDTH * scale);
+        }
+
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::command::Command;
+    use crate::mock::display;
+
+    fn pixels(log: &crate::mock::Log) -> Vec<Vec<u8>> {
+        log.commands()
+            .into_iter()
+            .filter(|(cmd, _)| *cmd == Command::RAMWR.value())
+            .map(|(_, data)| data.chunks(2).map(|c| (c[0] == 0xFF) as u8).collect())
+            .collect()
+    }
+
+    #[test]
+    fn char_cell_bits() {
+        let (mut display, _, log) = display(240, 320);
+        display.draw_char(0, 0, 'I', 0xFFFF, 0x0000, 1).unwrap();
+
+        let cell = &pixels(&log)[0];
+        assert_eq!(cell.len(), 6 * 8);
+        // 'I': top row has columns 1 to 3 set, the middle column runs down 7 rows
+        assert_eq!(cell[..6], [0, 1, 1, 1, 0, 0]);
+        assert_eq!(cell[6..12], [0, 0, 1, 0, 0, 0]);
+        assert_eq!(cell[42..], [0; 6]);
+    }
+
+    #[test]
+    fn scaled_text_advances() {
+        let (mut display, _, log) = display(240, 320);
+        display.draw_text(0, 0, "A\u{e9}\nB", 0xFFFF, 0x0000, 2).unwrap();
+
+        let columns: Vec<Vec<u8>> = log
+            .commands()
+            .into_iter()
+            .filter(|(cmd, _)| *cmd == Command::CASET.value() || *cmd == Command::RASET.value())
+            .map(|(_, params)| params)
+            .collect();
+        assert_eq!(columns[0], [0, 0, 0, 11]);
+        assert_eq!(columns[2], [0, 12, 0, 23]);
+        assert_eq!(columns[5], [0, 16, 0, 31]);
+        assert_eq!(pixels(&log)[0].len(), 12 * 16);
+    }
+}
@@ -4,7 +4,7 @@ use embedded_graphics::image::{Image, ImageDimensions, IntoPixelIter};
 use embedded_graphics::pixelcolor::raw::{RawData, RawU16};
 use embedded_graphics::pixelcolor::Rgb565;
 use embedded_graphics::prelude::{DrawTarget, Size};
-use embedded_graphics::primitives::Rectangle;
+use embedded_graphics::primitives::{Circle, Line, Rectangle, Triangle};
 use embedded_graphics::style::{PrimitiveStyle, Styled};
 
 use embedded_hal::blocking::spi;
@@ -12,6 +12,47 @@ use embedded_hal::digital::v2::OutputPin;
 
 use crate::{Error, ST7789V};
 
+/// Flattens `Rgb565` colors into the two big endian bytes the panel expects, lazily, so
+/// fills stream without an intermediate buffer (what `write_colors` does for `u16`).
+pub(crate) struct Rgb565ToBeBytes<I> {
+    colors: I,
+    low: Option<u8>,
+}
+
+impl<I> Rgb565ToBeBytes<I>
+where
+    I: Iterator<Item = Rgb565>,
+{
+    pub(crate) fn new(colors: I) -> Self {
+        Rgb565ToBeBytes { colors, low: None }
+    }
+}
+
+impl<I> Iterator for Rgb565ToBeBytes<I>
+where
+    I: Iterator<Item = Rgb565>,
+{
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        if let Some(low) = self.low.take() {
+            return Some(low);
+        }
+        let [high, low] = RawU16::from(self.colors.next()?).into_inner().to_be_bytes();
+        self.low = Some(low);
+        Some(high)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let pending = self.low.is_some() as usize;
+        let (lower, upper) = self.colors.size_hint();
+        (
+            lower.saturating_mul(2).saturating_add(pending),
+            upper.and_then(|u| u.checked_mul(2)?.checked_add(pending)),
+        )
+    }
+}
+
 impl<SPI, CS, DC, RST, PinError, SpiError> ST7789V<SPI, CS, DC, RST, PinError, SpiError>
 where
     SPI: spi::Write<u8, Error = SpiError>,
@@ -19,17 +60,99 @@ where
     DC: OutputPin<Error = PinError>,
     RST: OutputPin<Error = PinError>,
 {
-    fn fill_rect(
+    /// Stream the pixels of a filled rectangle (`bottom_right` inclusive) into one
+    /// window, clipped to the screen so rectangles hanging over an edge neither wrap
+    /// around nor fail.
+    fn fill_rect<I>(
         &mut self,
         item: &dyn Dimensions,
-        colors: &mut dyn Iterator<Item=u16>,
-    ) -> Result<(), Error<PinError, SpiError>> {
-        let sx = item.top_left().x as u16;
-        let sy = item.top_left().y as u16;
-        let ex = item.bottom_right().x as u16;
-        let ey = item.bottom_right().y as u16;
+        pixels: I,
+    ) -> Result<(), Error<PinError, SpiError>>
+    where
+        I: Iterator<Item = Pixel<Rgb565>>,
+    {
+        let (top_left, bottom_right) = (item.top_left(), item.bottom_right());
+        let sx = top_left.x.max(0);
+        let sy = top_left.y.max(0);
+        let ex = (bottom_right.x + 1).min(self.width as i32);
+        let ey = (bottom_right.y + 1).min(self.height as i32);
+        if sx >= ex || sy >= ey {
+            return Ok(());
+        }
+        let colors = pixels
+            .filter(|p| (sx..ex).contains(&p.0.x) && (sy..ey).contains(&p.0.y))
+            .map(|p| p.1);
 
-        self.pixels(sx, sy, ex, ey, colors)?;
+        self.fill_window(sx as u16, sy as u16, ex as u16, ey as u16, colors)
+    }
+
+    /// Stream `colors` row by row into the window `sx`, `sy` to `ex`, `ey`.
+    fn fill_window<I>(
+        &mut self,
+        sx: u16,
+        sy: u16,
+        ex: u16,
+        ey: u16,
+        colors: I,
+    ) -> Result<(), Error<PinError, SpiError>>
+    where
+        I: Iterator<Item = Rgb565>,
+    {
+        self.address_window(sx, sy, ex, ey)?;
+        if !self.plain_colors() {
+            self.write_colors(colors.map(|c| RawU16::from(c).into_inner()))?;
+        } else {
+            self.write_bytes(Rgb565ToBeBytes::new(colors))?;
+        }
+
+        Ok(())
+    }
+
+    /// Draw pixels, merging horizontal runs of the same color into one `pixels` window.
+    ///
+    /// Filled shapes and horizontal lines come out of embedded-graphics scanline by
+    /// scanline, so most of their pixels end up in runs. Scattered pixels fall back to
+    /// `pixel_unchecked`. Off screen pixels are clipped like in `draw_pixel`.
+    fn draw_spans<I>(&mut self, pixels: I) -> Result<(), Error<PinError, SpiError>>
+    where
+        I: IntoIterator<Item = Pixel<Rgb565>>,
+    {
+        // current run as (y, first x, last x, color)
+        let mut run: Option<(u16, u16, u16, u16)> = None;
+        for Pixel(point, color) in pixels {
+            let (x, y) = (point.x, point.y);
+            if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+                continue;
+            }
+            let (x, y, color) = (x as u16, y as u16, RawU16::from(color).into_inner());
+            match run.as_mut() {
+                Some((ry, _, last, rc)) if *ry == y && *last + 1 == x && *rc == color => {
+                    *last = x;
+                }
+                _ => {
+                    if let Some(done) = run.replace((y, x, x, color)) {
+                        self.write_span(done)?;
+                    }
+                }
+            }
+        }
+        if let Some(done) = run {
+            self.write_span(done)?;
+        }
+
+        Ok(())
+    }
+
+    fn write_span(
+        &mut self,
+        (y, first, last, color): (u16, u16, u16, u16),
+    ) -> Result<(), Error<PinError, SpiError>> {
+        if first == last {
+            self.pixel_unchecked(first, y, color)?;
+        } else {
+            let count = (last - first + 1) as usize;
+            self.pixels(first, y, last + 1, y + 1, &mut core::iter::repeat_n(color, count))?;
+        }
 
         Ok(())
     }
@@ -47,10 +170,13 @@ where
 
     fn draw_pixel(&mut self, pixel: Pixel<Rgb565>) -> Result<(), Self::Error> {
         let color = RawU16::from(pixel.1).into_inner();
-        let x = pixel.0.x as u16;
-        let y = pixel.0.y as u16;
+        let (x, y) = (pixel.0.x, pixel.0.y);
 
-        self.pixel(x, y, color)?;
+        // shapes may reach past the edges, clip those pixels instead of failing the draw
+        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+            return Ok(());
+        }
+        self.pixel_unchecked(x as u16, y as u16, color)?;
 
         Ok(())
     }
@@ -60,15 +186,19 @@ where
         Size::new(self.width as u32, self.height as u32)
     }
 
+    fn clear(&mut self, color: Rgb565) -> Result<(), Self::Error> {
+        self.fill_screen(RawU16::from(color).into_inner())?;
+
+        Ok(())
+    }
+
     fn draw_rectangle(
         &mut self,
         item: &Styled<Rectangle, PrimitiveStyle<Rgb565>>,
     ) -> Result<(), Self::Error> {
         // filled rect can be rendered into frame window directly
         if item.style.fill_color.is_some() {
-            let mut colors = item.into_iter().map(|p| RawU16::from(p.1).into_inner());
-
-            self.fill_rect(item, &mut colors)
+            self.fill_rect(item, item.into_iter())
         } else if let Some(_color) = item.style.stroke_color {
             if item.style.stroke_width == 0 {
                 return Ok(()); // nothing to draw
@@ -83,6 +213,27 @@ where
         }
     }
 
+    fn draw_line(
+        &mut self,
+        item: &Styled<Line, PrimitiveStyle<Rgb565>>,
+    ) -> Result<(), Self::Error> {
+        self.draw_spans(item)
+    }
+
+    fn draw_triangle(
+        &mut self,
+        item: &Styled<Triangle, PrimitiveStyle<Rgb565>>,
+    ) -> Result<(), Self::Error> {
+        self.draw_spans(item)
+    }
+
+    fn draw_circle(
+        &mut self,
+        item: &Styled<Circle, PrimitiveStyle<Rgb565>>,
+    ) -> Result<(), Self::Error> {
+        self.draw_spans(item)
+    }
+
     fn draw_image<'a, 'b, I>(&mut self, item: &'a Image<'b, I, Rgb565>) -> Result<(), Self::Error>
         where
             &'b I: IntoPixelIter<Rgb565>,
@@ -90,22 +241,165 @@ where
     {
         // TODO: this is inconsistent in embedded-graphics between Rectangle and Image
         // See: https://github.com/jamwaffles/embedded-graphics/issues/182
-        let sx = item.top_left().x as u16;
-        let sy = item.top_left().y as u16;
-        let ex = (item.bottom_right().x) as u16;
-        let ey = (item.bottom_right().y) as u16;
-
         let mut count = 0u32;
         let max = item.size().width * item.size().height;
-        let mut colors = item
+
+        // only the part on screen is streamed, into a window of just that part, so images
+        // hanging over an edge neither wrap around nor fail
+        let top_left = item.top_left();
+        let sx = top_left.x.max(0);
+        let sy = top_left.y.max(0);
+        let ex = (top_left.x + item.size().width as i32).min(self.width as i32);
+        let ey = (top_left.y + item.size().height as i32).min(self.height as i32);
+        if sx >= ex || sy >= ey {
+            return Ok(());
+        }
+        let colors = item
             .into_iter()
             .take_while(|_| {
                 count += 1;
                 count <= max
             })
-            .map(|p| RawU16::from(p.1).into_inner());
-        self.pixels(sx, sy, ex, ey, &mut colors)?;
+            .filter(|p| (sx..ex).contains(&p.0.x) && (sy..ey).contains(&p.0.y))
+            .map(|p| p.1);
 
-        Ok(())
+        self.fill_window(sx as u16, sy as u16, ex as u16, ey as u16, colors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_graphics::pixelcolor::{Rgb565, RgbColor};
+    use embedded_graphics::prelude::*;
+    use embedded_graphics::primitives::{Circle, Line, Rectangle};
+    use embedded_graphics::style::PrimitiveStyle;
+
+    use super::Rgb565ToBeBytes;
+    use crate::command::Command;
+    use crate::mock::display;
+
+    #[test]
+    fn colors_to_be_bytes() {
+        let bytes = Rgb565ToBeBytes::new([Rgb565::RED, Rgb565::GREEN].iter().copied());
+        assert_eq!(bytes.size_hint(), (4, Some(4)));
+        assert_eq!(bytes.collect::<Vec<u8>>(), [0xF8, 0x00, 0x07, 0xE0]);
+    }
+
+    #[test]
+    fn filled_circle_in_spans() {
+        let (mut display, _, log) = display(240, 320);
+        Circle::new(Point::new(20, 20), 5)
+            .into_styled(PrimitiveStyle::with_fill(Rgb565::RED))
+            .draw(&mut display)
+            .unwrap();
+
+        let writes = log.commands().into_iter().filter(|(cmd, _)| *cmd == Command::RAMWR.value());
+        // one window per scanline instead of one per pixel
+        assert_eq!(writes.count(), 11);
+    }
+
+    #[test]
+    fn line_clipped_and_batched() {
+        let (mut display, _, log) = display(240, 320);
+        Line::new(Point::new(-5, 10), Point::new(300, 10))
+            .into_styled(PrimitiveStyle::with_stroke(Rgb565::GREEN, 1))
+            .draw(&mut display)
+            .unwrap();
+
+        let commands = log.commands();
+        assert_eq!(commands[0], (Command::CASET.value(), vec![0, 0, 0, 239]));
+        assert_eq!(commands[2].1.len(), 240 * 2);
+    }
+
+    #[test]
+    fn rotated_single_pixel_lines_up_with_run() {
+        let (mut display, _, log) = display(240, 320);
+        display.set_rotate(crate::Rotate::Rotate90).unwrap();
+        log.clear();
+        let pixels = [
+            Pixel(Point::new(5, 10), Rgb565::RED),
+            Pixel(Point::new(6, 10), Rgb565::GREEN),
+            Pixel(Point::new(7, 10), Rgb565::GREEN),
+            Pixel(Point::new(8, 10), Rgb565::GREEN),
+        ];
+        display.draw_spans(pixels.iter().copied()).unwrap();
+
+        // MADCTL does the rotating, the pixel at 5 stays right before the run 6..=8
+        let columns: Vec<Vec<u8>> = log
+            .commands()
+            .into_iter()
+            .filter(|(cmd, _)| *cmd == Command::CASET.value())
+            .map(|(_, params)| params)
+            .collect();
+        assert_eq!(columns, [vec![0, 5, 0, 5], vec![0, 6, 0, 8]]);
+    }
+
+    #[test]
+    fn filled_rectangle_streams() {
+        let (mut display, _, log) = display(240, 320);
+        Rectangle::new(Point::new(0, 0), Point::new(99, 1))
+            .into_styled(PrimitiveStyle::with_fill(Rgb565::BLUE))
+            .draw(&mut display)
+            .unwrap();
+
+        let commands = log.commands();
+        assert_eq!(commands[0], (Command::CASET.value(), vec![0, 0, 0, 99]));
+        assert_eq!(commands[1], (Command::RASET.value(), vec![0, 0, 0, 1]));
+        let (_, data) = commands.iter().find(|(cmd, _)| *cmd == Command::RAMWR.value()).unwrap();
+        assert_eq!(data.len(), 100 * 2 * 2);
+        assert!(data.chunks(2).all(|c| c == [0x00, 0x1F]));
+    }
+
+    #[test]
+    fn filled_rectangle_clipped() {
+        let (mut display, _, log) = display(240, 320);
+        Rectangle::new(Point::new(-5, 318), Point::new(4, 330))
+            .into_styled(PrimitiveStyle::with_fill(Rgb565::BLUE))
+            .draw(&mut display)
+            .unwrap();
+        Rectangle::new(Point::new(-20, 5), Point::new(-1, 9))
+            .into_styled(PrimitiveStyle::with_fill(Rgb565::BLUE))
+            .draw(&mut display)
+            .unwrap();
+
+        let commands = log.commands();
+        assert_eq!(commands.len(), 3);
+        assert_eq!(commands[0], (Command::CASET.value(), vec![0, 0, 0, 4]));
+        assert_eq!(commands[1], (Command::RASET.value(), vec![0x01, 0x3E, 0x01, 0x3F]));
+        assert_eq!(commands[2].1.len(), 5 * 2 * 2);
+    }
+
+    #[test]
+    fn clear_ignores_rotation() {
+        let (mut display, _, log) = display(240, 320);
+        display.set_rotate(crate::Rotate::Rotate270).unwrap();
+        log.clear();
+        display.clear(Rgb565::RED).unwrap();
+
+        let commands = log.commands();
+        assert_eq!(commands[0], (Command::CASET.value(), vec![0, 0, 0, 239]));
+        assert_eq!(commands[1], (Command::RASET.value(), vec![0, 0, 0x01, 0x3F]));
+        assert_eq!(commands[2].1.len(), 240 * 320 * 2);
+        assert!(commands[2].1.chunks(2).all(|c| c == [0xF8, 0x00]));
+    }
+
+    #[test]
+    fn image_clipped_to_screen() {
+        use embedded_graphics::image::{Image, ImageRawBE};
+
+        // 4x2 image, pixel value = index
+        let data: Vec<u8> = (0..8u16).flat_map(u16::to_be_bytes).collect();
+        let raw = ImageRawBE::<Rgb565>::new(&data, 4, 2);
+        let (mut display, _, log) = display(240, 320);
+        Image::new(&raw, Point::new(238, -1)).draw(&mut display).unwrap();
+
+        let commands = log.commands();
+        assert_eq!(commands[0], (Command::CASET.value(), vec![0, 238, 0, 239]));
+        assert_eq!(commands[1], (Command::RASET.value(), vec![0, 0, 0, 0]));
+        assert_eq!(commands[2], (Command::RAMWR.value(), vec![0, 4, 0, 5]));
+
+        log.clear();
+        Image::new(&raw, Point::new(-10, 0)).draw(&mut display).unwrap();
+        assert!(log.commands().is_empty());
     }
 }
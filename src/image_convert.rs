@@ -0,0 +1,55 @@
+//! Conversion of decoded images (PNG, JPEG, BMP, ...) into raw display data.
+
+use image::DynamicImage;
+
+use crate::color::rgb888_to_rgb565;
+
+/// Converts an image to RGB565, two big endian bytes per pixel, row by row.
+///
+/// The result can be passed straight to `ST7789V::write_raw_image` together with
+/// `img.width()` and `img.height()`. Transparency is dropped.
+pub fn to_rgb565(img: &DynamicImage) -> Vec<u8> {
+    let rgb = img.to_rgb8();
+    let mut out = Vec::with_capacity((rgb.width() * rgb.height() * 2) as usize);
+
+    for pixel in rgb.pixels() {
+        let [r, g, b] = pixel.0;
+        let color = rgb888_to_rgb565(r, g, b);
+        out.extend_from_slice(&color.to_be_bytes());
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{DynamicImage, Rgb, RgbImage, Rgba, RgbaImage};
+
+    use super::to_rgb565;
+
+    #[test]
+    fn known_colors() {
+        let mut img = RgbImage::new(3, 2);
+        img.put_pixel(0, 0, Rgb([0xFF, 0xFF, 0xFF]));
+        img.put_pixel(1, 0, Rgb([0xFF, 0x00, 0x00]));
+        img.put_pixel(2, 0, Rgb([0x00, 0xFF, 0x00]));
+        // the lowest step of each channel, and just below it
+        img.put_pixel(0, 1, Rgb([0x08, 0x04, 0x08]));
+        img.put_pixel(1, 1, Rgb([0x07, 0x03, 0x07]));
+        // no rounding up, 0xFE blue and 0x81 green truncate
+        img.put_pixel(2, 1, Rgb([0x00, 0x81, 0xFE]));
+
+        #[rustfmt::skip]
+        let expected = [
+            0xFF, 0xFF, 0xF8, 0x00, 0x07, 0xE0,
+            0x08, 0x21, 0x00, 0x00, 0x04, 0x1F,
+        ];
+        assert_eq!(to_rgb565(&DynamicImage::ImageRgb8(img)), expected);
+    }
+
+    #[test]
+    fn alpha_dropped() {
+        let img = RgbaImage::from_pixel(1, 1, Rgba([0x00, 0x00, 0xFF, 0x00]));
+        assert_eq!(to_rgb565(&DynamicImage::ImageRgba8(img)), [0x00, 0x1F]);
+    }
+}
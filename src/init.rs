@@ -0,0 +1,160 @@
+//! Init sequences described as data, so panel variants can bring their own.
+
+use embedded_hal::blocking::delay::DelayMs;
+use embedded_hal::blocking::spi;
+use embedded_hal::digital::v2::OutputPin;
+
+use crate::command::Command;
+use crate::{
+    Error, DEFAULT_COLMOD, DEFAULT_MADCTL, DEFAULT_VCOM, DEFAULT_VDV, DEFAULT_VRH, ST7789V,
+};
+
+/// One step of an init sequence: a command, its parameters and a delay afterwards
+#[derive(Clone, Copy, Debug)]
+pub struct InitStep {
+    /// Command to send
+    pub cmd: Command,
+    /// Parameters sent as data after the command, may be empty
+    pub params: &'static [u8],
+    /// Delay after the command in milliseconds, 0 for none
+    pub delay_ms: u16,
+}
+
+impl InitStep {
+    /// Create a step
+    pub const fn new(cmd: Command, params: &'static [u8], delay_ms: u16) -> Self {
+        InitStep {
+            cmd,
+            params,
+            delay_ms,
+        }
+    }
+}
+
+/// PORCTRL parameters of `DEFAULT_INIT`: back porch, front porch, PSEN, idle and partial
+/// porches
+pub const DEFAULT_PORCH: [u8; 5] = [0x0C, 0x0C, 0x00, 0x33, 0x33];
+
+/// LCMCTRL value of `DEFAULT_INIT` and `lcm_control`
+pub const DEFAULT_LCMCTRL: u8 = 0x2C;
+
+/// FRCTRL2 value of `DEFAULT_INIT` and `lcm_control`, 60 Hz with the default porches
+pub const DEFAULT_FRCTRL2: u8 = 0x0F;
+
+/// PWCTRL1 parameters of `DEFAULT_INIT` and `lcm_control`
+pub const DEFAULT_PWCTRL1: [u8; 2] = [0xA4, 0xA1];
+
+/// Positive voltage gamma table (E0) of `DEFAULT_INIT`
+pub const DEFAULT_GAMMA_POSITIVE: [u8; 14] = [
+    0xD0, 0x08, 0x11, 0x08, 0x0C, 0x15, 0x39, 0x33, 0x50, 0x36, 0x13, 0x14, 0x29, 0x2D,
+];
+
+/// Negative voltage gamma table (E1) of `DEFAULT_INIT`
+pub const DEFAULT_GAMMA_NEGATIVE: [u8; 14] = [
+    0xD0, 0x08, 0x10, 0x08, 0x06, 0x06, 0x39, 0x44, 0x51, 0x0B, 0x16, 0x14, 0x2F, 0x31,
+];
+
+/// Init sequence of the Waveshare 2inch LCD module, used by `ST7789V::init`
+pub const DEFAULT_INIT: &[InitStep] = &[
+    InitStep::new(Command::MADCTL, &[DEFAULT_MADCTL], 0),
+    InitStep::new(Command::COLMOD, &[DEFAULT_COLMOD], 0),
+    InitStep::new(Command::INVON, &[], 0),
+    InitStep::new(Command::CASET, &[0x00, 0x00, 0x01, 0x3F], 0),
+    InitStep::new(Command::RASET, &[0x00, 0x00, 0x00, 0xEF], 0),
+    InitStep::new(Command::PORCTRL, &DEFAULT_PORCH, 0),
+    InitStep::new(Command::GCTRL, &[0x35], 0),
+    InitStep::new(Command::VCOMS, &[DEFAULT_VCOM], 0),
+    InitStep::new(Command::LCMCTRL, &[DEFAULT_LCMCTRL], 0),
+    InitStep::new(Command::VDVVRHEN, &[0x01], 0),
+    InitStep::new(Command::VRHS, &[DEFAULT_VRH], 0),
+    InitStep::new(Command::VDVS, &[DEFAULT_VDV], 0),
+    InitStep::new(Command::FRCTRL2, &[DEFAULT_FRCTRL2], 0),
+    InitStep::new(Command::PWCTRL1, &DEFAULT_PWCTRL1, 0),
+    InitStep::new(Command::E0, &DEFAULT_GAMMA_POSITIVE, 0),
+    InitStep::new(Command::E1, &DEFAULT_GAMMA_NEGATIVE, 0),
+    InitStep::new(Command::INVON, &[], 0),
+    InitStep::new(Command::SLPOUT, &[], 0),
+    InitStep::new(Command::DISPON, &[], 0),
+];
+
+/// Sum of the step delays of `seq` in milliseconds
+pub const fn sequence_delay_ms(seq: &[InitStep]) -> u32 {
+    let mut total = 0;
+    let mut i = 0;
+    while i < seq.len() {
+        total += seq[i].delay_ms as u32;
+        i += 1;
+    }
+    total
+}
+
+/// Milliseconds `ST7789V::init` spends waiting: the reset pulse and the 120 ms after it,
+/// the step delays of `DEFAULT_INIT` and the 120 ms between SLPOUT and DISPON. Bus time
+/// comes on top but is small, about 100 bytes. For budgeting a boot sequence, e.g. to
+/// bring up other peripherals while the panel settles.
+pub const INIT_DELAY_MS: u32 = 1 + 1 + 120 + sequence_delay_ms(DEFAULT_INIT) + 120;
+
+impl<SPI, CS, DC, RST, PinError, SpiError> ST7789V<SPI, CS, DC, RST, PinError, SpiError>
+where
+    SPI: spi::Write<u8, Error = SpiError>,
+    CS: OutputPin<Error = PinError>,
+    DC: OutputPin<Error = PinError>,
+    RST: OutputPin<Error = PinError>,
+{
+    /// Send every step of `seq` in order, waiting each step's delay after it.
+    pub fn run_sequence<DELAY>(
+        &mut self,
+        seq: &[InitStep],
+        delay: &mut DELAY,
+    ) -> Result<&mut Self, Error<PinError, SpiError>>
+    where
+        DELAY: DelayMs<u16>,
+    {
+        for step in seq {
+            let params = if step.params.is_empty() {
+                None
+            } else {
+                Some(step.params)
+            };
+            self.command(step.cmd, params)?;
+            if step.delay_ms > 0 {
+                delay.delay_ms(step.delay_ms);
+            }
+        }
+
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::InitStep;
+    use crate::command::Command;
+    use crate::mock::{display, Event};
+
+    #[test]
+    fn sequence_commands_and_delays() {
+        let (mut display, mut delay, log) = display(240, 320);
+        let seq = [
+            InitStep::new(Command::SWRESET, &[], 150),
+            InitStep::new(Command::COLMOD, &[0x55], 10),
+            InitStep::new(Command::NORON, &[], 0),
+        ];
+        display.run_sequence(&seq, &mut delay).unwrap();
+
+        assert_eq!(
+            log.commands(),
+            [
+                (Command::SWRESET.value(), vec![]),
+                (Command::COLMOD.value(), vec![0x55]),
+                (Command::NORON.value(), vec![]),
+            ]
+        );
+        let delays: Vec<Event> = log
+            .events()
+            .into_iter()
+            .filter(|e| matches!(e, Event::Delay(_)))
+            .collect();
+        assert_eq!(delays, [Event::Delay(150), Event::Delay(10)]);
+    }
+}
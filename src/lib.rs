@@ -1,18 +1,72 @@
-#![deny(unsafe_code, warnings)]
+#![deny(unsafe_code)]
+#![cfg_attr(feature = "strict", deny(warnings))]
 // #![no_std]
 
 use core::marker::PhantomData;
 
-use embedded_hal::blocking::delay::DelayMs;
+use embedded_hal::blocking::delay::{DelayMs, DelayUs};
 use embedded_hal::blocking::spi;
 use embedded_hal::digital::v2::OutputPin;
 
 mod command;
-use crate::command::Command;
+pub use crate::command::Command;
 
 #[cfg(feature = "graphics")]
 mod graphics;
 
+#[cfg(feature = "image")]
+pub mod image_convert;
+
+mod init;
+pub use crate::init::{
+    sequence_delay_ms, InitStep, DEFAULT_FRCTRL2, DEFAULT_GAMMA_NEGATIVE, DEFAULT_GAMMA_POSITIVE,
+    DEFAULT_INIT, DEFAULT_LCMCTRL, DEFAULT_PORCH, DEFAULT_PWCTRL1, INIT_DELAY_MS,
+};
+
+mod read;
+pub use crate::read::{rgb666_to_rgb565, Madctl, PixelFormat, PowerMode, SelfDiag};
+
+mod scroll;
+pub use crate::scroll::ScrollRegion;
+
+mod rgb;
+pub use crate::rgb::{DisplayOperation, RgbInterfaceConfig, RgbSyncMode};
+
+mod tearing;
+pub use crate::tearing::TE_TIMEOUT_US;
+
+mod transaction;
+pub use crate::transaction::Transaction;
+
+mod shared;
+pub use crate::shared::SharedSpi;
+
+mod font;
+pub use crate::font::{CHAR_HEIGHT, CHAR_WIDTH, MAX_SCALE};
+
+mod with_delay;
+pub use crate::with_delay::WithDelay;
+
+mod fast_fill;
+
+mod config;
+pub use crate::config::DisplayConfig;
+
+mod writer;
+pub use crate::writer::PixelWriter;
+
+pub mod color;
+pub use crate::color::rgb888_to_rgb565;
+
+mod encoding;
+use crate::encoding::Encoder;
+pub use crate::encoding::{Bits12, Bits16, Bits18, PixelEncoding};
+
+#[cfg(feature = "graphics")]
+mod buffered;
+#[cfg(feature = "graphics")]
+pub use crate::buffered::BufferedDisplay;
+
 /// Errors
 #[derive(Debug)]
 pub enum Error<PinError, SpiError> {
@@ -20,6 +74,14 @@ pub enum Error<PinError, SpiError> {
     InvalidColumnAddress,
     /// Invalid row address
     InvalidRowAddress,
+    /// Width or height larger than the 240x320 frame memory
+    InvalidSize,
+    /// Buffer length does not match what the operation writes
+    InvalidBufferSize,
+    /// The panel did not answer a read, see `ping_read`
+    NoResponse,
+    /// A register read back differently than written, see `check_communication`
+    ReadbackMismatch,
     /// Pin error
     Pin(PinError),
     /// SPI error
@@ -50,7 +112,132 @@ pub enum ColorFormat {
     RGB262K_CI16MTrunc = 0b0110_0111,
 }
 
+/// WRCTRLD brightness control block on (BCTRL)
+const WRCTRLD_BCTRL: u8 = 0b0010_0000;
+/// WRCTRLD display dimming on (DD)
+const WRCTRLD_DD: u8 = 0b0000_1000;
+/// WRCTRLD backlight control on (BL)
+const WRCTRLD_BL: u8 = 0b0000_0100;
+
+/// Row offset for 240x240 modules mounted with the gate scan reversed (MY, `Flip::Vertical`
+/// or `Flip::Both`), see `ST7789V::set_row_offset`. The glass then shows frame memory
+/// rows 80 to 319 instead of 0 to 239.
+pub const ROW_OFFSET_240X240: u16 = 320 - 240;
+
+/// GATECTRL gate scan direction reversed (GS)
+pub const GATECTRL_GS: u8 = 0b0000_0001;
+/// GATECTRL interlaced gate scan (SM)
+pub const GATECTRL_SM: u8 = 0b0000_0100;
+/// GATECTRL mirror gate timing (TMG)
+pub const GATECTRL_TMG: u8 = 0b0001_0000;
+
+/// Content adaptive brightness control mode (WRCACE C1..C0)
+///
+/// CABC works by scaling the LEDPWM output of the controller, so it only has an effect on
+/// modules whose backlight driver is wired to LEDPWM. Modules that switch the backlight
+/// from an MCU pin (like the Waveshare 2inch module) ignore it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CabcMode {
+    /// CABC off
+    Off = 0b00,
+    /// User interface image, least aggressive
+    UserInterface = 0b01,
+    /// Still picture
+    StillPicture = 0b10,
+    /// Moving image, most aggressive
+    MovingImage = 0b11,
+}
+
+impl CabcMode {
+    pub fn value(self) -> u8 {
+        self as u8
+    }
+}
+
+/// Display mode as last set through the driver, see `ST7789V::current_mode`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DisplayMode {
+    /// Normal display mode (NORON), the whole panel shows frame memory
+    Normal,
+    /// Partial mode (PTLON), only the partial area (PTLAR) is refreshed
+    Partial,
+}
+
+/// Frame rate settings picked by `ST7789V::configure_refresh`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RefreshConfig {
+    /// RTNA, FRCTRL2 line period (0 - 31)
+    pub rtna: u8,
+    /// BPA, PORCTRL back porch in lines
+    pub back_porch: u8,
+    /// FPA, PORCTRL front porch in lines
+    pub front_porch: u8,
+    /// Resulting frame rate in mHz
+    pub millihertz: u32,
+}
+
+/// Normal mode frame rate in mHz for RTNA `rtna` and `porch` lines of front plus back porch
+fn frame_rate_mhz(rtna: u8, porch: u16) -> u32 {
+    (10_000_000_000u64 / ((250 + rtna as u64 * 16) * (320 + porch as u64))) as u32
+}
+
+/// Logical state of the driver, see `ST7789V::state`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DriverState {
+    /// Out of sleep (SLPOUT sent since the last SLPIN or reset)
+    pub awake: bool,
+    /// Display inversion, as last set or as `init` will apply it
+    pub inverted: bool,
+    /// Idle mode (8 color) on
+    pub idle: bool,
+    /// Normal or partial mode
+    pub mode: DisplayMode,
+    /// Rotation set with `set_rotate`
+    pub rotation: Rotate,
+    /// COLMOD value as last written
+    pub color_format: PixelFormat,
+}
+
+/// Address window and MADCTL as last written by the driver, see `ST7789V::debug_state`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WindowDebug {
+    /// Columns `start..end` (end exclusive) of the last CASET, `None` since reset
+    pub columns: Option<(u16, u16)>,
+    /// Rows `start..end` (end exclusive) of the last RASET, `None` since reset
+    pub rows: Option<(u16, u16)>,
+    /// MADCTL value
+    pub madctl: u8,
+    /// Rotation set with `set_rotate`, its MY/MX bits are part of `madctl`
+    pub rotation: Rotate,
+}
+
+/// Panel mirroring on top of the rotation, see `ST7789V::set_flip`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Flip {
+    /// No mirroring
+    None,
+    /// Mirror left to right (MX)
+    Horizontal,
+    /// Mirror top to bottom (MY)
+    Vertical,
+    /// Mirror both ways (MX and MY), for modules mounted upside down and mirrored
+    Both,
+}
+
+impl Flip {
+    /// MADCTL MY/MX bits
+    pub fn value(self) -> u8 {
+        match self {
+            Flip::None => 0b0000_0000,
+            Flip::Horizontal => 0b0100_0000,
+            Flip::Vertical => 0b1000_0000,
+            Flip::Both => 0b1100_0000,
+        }
+    }
+}
+
 /// Rotate Rotate0 Rotate90 Rotate180 Rotate270
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Rotate {
     Rotate0 = 0,
     Rotate90 = 90,
@@ -188,18 +375,6 @@ impl MemAccCtrlConfig {
     // ############################
 
 
-    pub fn default() -> Self {
-
-        MemAccCtrlConfig {
-            color_order: ColorOrder::Rgb,
-            latch_order: LatchOrder::RightToLeft, // MIRROR_HORIZONTAL 水平镜像
-            line_order: LineAddressOrder::TopToBottom,
-            page_order: PageAddressOrder::BottomToTop, // Y
-            page_column_order: PageColumnOrder::ReverseMode, // MIRROR_VERTICAL 垂直镜像
-            column_order: ColumnAddressOrder::RightToLeft, // x
-        }
-    }
-
     pub fn rotate_0() -> Self {
         MemAccCtrlConfig {
             color_order: ColorOrder::Rgb,
@@ -284,6 +459,34 @@ impl MemAccCtrlConfig {
     }
 }
 
+/// MADCTL byte of `MemAccCtrlConfig::rotate_0` .. `rotate_270` for `rotate`, as a
+/// constant so the four values can be checked against the diagrams above
+pub const fn madctl_for(rotate: Rotate) -> u8 {
+    match rotate {
+        // neither MY nor MX: top to bottom, right to left
+        Rotate::Rotate0 => 0b0000_0000,
+        // MY: bottom to top, right to left
+        Rotate::Rotate90 => 0b1000_0000,
+        // MY MX: bottom to top, left to right
+        Rotate::Rotate180 => 0b1100_0000,
+        // MX: top to bottom, left to right
+        Rotate::Rotate270 => 0b0100_0000,
+    }
+}
+
+impl Default for MemAccCtrlConfig {
+    fn default() -> Self {
+        MemAccCtrlConfig {
+            color_order: ColorOrder::Rgb,
+            latch_order: LatchOrder::RightToLeft, // MIRROR_HORIZONTAL 水平镜像
+            line_order: LineAddressOrder::TopToBottom,
+            page_order: PageAddressOrder::BottomToTop, // Y
+            page_column_order: PageColumnOrder::ReverseMode, // MIRROR_VERTICAL 垂直镜像
+            column_order: ColumnAddressOrder::RightToLeft, // x
+        }
+    }
+}
+
 /// ST7789V display driver config
 pub struct ST7789VConfig<CS, DC, RST>
     where
@@ -325,6 +528,98 @@ impl<CS, DC, RST> ST7789VConfig<CS, DC, RST>
     }
 }
 
+/// Number of columns in the controller frame memory, see `scroll::GRAM_LINES` for rows
+const GRAM_COLUMNS: u16 = 240;
+
+/// Whether a `width` x `height` panel (before rotation) fits the frame memory
+fn fits_gram(width: u16, height: u16) -> bool {
+    width <= GRAM_COLUMNS && height <= scroll::GRAM_LINES
+}
+
+/// VCOMS value used by `init` unless changed with `set_vcom`
+pub(crate) const DEFAULT_VCOM: u8 = 0x1F;
+/// MADCTL value used by `init` unless changed, as in `DEFAULT_INIT`
+pub(crate) const DEFAULT_MADCTL: u8 = 0x00;
+/// COLMOD value used by `init` unless changed, as in `DEFAULT_INIT`
+pub(crate) const DEFAULT_COLMOD: u8 = 0x05;
+/// VRHS value used by `init` and `lcm_control` unless changed with `set_vrh`
+pub(crate) const DEFAULT_VRH: u8 = 0x12;
+/// VDVS value used by `init` and `lcm_control` unless changed with `set_vdv`
+pub(crate) const DEFAULT_VDV: u8 = 0x20;
+
+/// RGB565 to the RGB444 the panel takes in 12 bit mode, keeping the top four bits of
+/// each channel
+pub fn rgb565_to_rgb444(color: u16) -> u16 {
+    let r = (color >> 12) & 0xF;
+    let g = (color >> 7) & 0xF;
+    let b = (color >> 1) & 0xF;
+    (r << 8) | (g << 4) | b
+}
+
+/// `color` with each RGB565 channel scaled by its gain in 1/256, clamped to the channel
+/// maximum
+fn correct_color(color: u16, gains: [u16; 3]) -> u16 {
+    let scale = |value: u16, gain: u16, max: u32| (value as u32 * gain as u32 / 256).min(max) as u16;
+    let r = scale(color >> 11, gains[0], 0x1F);
+    let g = scale((color >> 5) & 0x3F, gains[1], 0x3F);
+    let b = scale(color & 0x1F, gains[2], 0x1F);
+    (r << 11) | (g << 5) | b
+}
+
+/// RGB565 colors as the bytes the panel expects, through the `PixelEncoding` of the
+/// current color format.
+struct PixelBytes<I> {
+    colors: I,
+    encoder: Encoder,
+    correction: Option<[u16; 3]>,
+    out: [u8; 3],
+    len: u8,
+    pos: u8,
+}
+
+impl<I> Iterator for PixelBytes<I>
+    where
+        I: Iterator<Item=u16>,
+{
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        if self.pos == self.len {
+            let correction = self.correction;
+            let colors = self.colors.by_ref().map(|c| match correction {
+                Some(gains) => correct_color(c, gains),
+                None => c,
+            });
+            let mut group = [0u16; 2];
+            let mut n = 0;
+            for (slot, color) in group[..self.encoder.pixels].iter_mut().zip(colors) {
+                *slot = color;
+                n += 1;
+            }
+            if n == 0 {
+                return None;
+            }
+            self.len = (self.encoder.encode)(&group[..n], &mut self.out) as u8;
+            self.pos = 0;
+        }
+        self.pos += 1;
+        Some(self.out[self.pos as usize - 1])
+    }
+}
+
+/// Color `num / den` of the way from `a` to `b`, each RGB565 channel on its own
+fn lerp_rgb565(a: u16, b: u16, num: u32, den: u32) -> u16 {
+    if den == 0 {
+        return a;
+    }
+    let channel = |shift: u16, mask: u16| {
+        let (ca, cb) = (((a >> shift) & mask) as u32, ((b >> shift) & mask) as u32);
+        let c = (ca * (den - num) + cb * num) / den;
+        (c as u16) << shift
+    };
+    channel(11, 0x1F) | channel(5, 0x3F) | channel(0, 0x1F)
+}
+
 /// ST7789V display driver
 pub struct ST7789V<SPI, CS, DC, RST, PinError, SpiError>
     where
@@ -341,8 +636,86 @@ pub struct ST7789V<SPI, CS, DC, RST, PinError, SpiError>
     _pin_err: PhantomData<PinError>,
     _spi_err: PhantomData<SpiError>,
     rotate: Rotate,
+    /// Mirroring set with `set_flip`, on top of `rotate`
+    flip: Flip,
     width: u16,
     height: u16,
+    /// Last value written to WRCTRLD
+    ctrl_display: u8,
+    /// Last value written to WRCACE
+    cabc: u8,
+    /// VCOMS value used by init, see `set_vcom`
+    vcom: u8,
+    /// Delay function and microseconds to wait after asserting CS, see `set_cs_setup_delay_us`
+    cs_setup: Option<(fn(u32), u32)>,
+    /// Delay function and microseconds to wait after RAMWR, see `set_ramwr_settle_us`
+    ramwr_settle: Option<(fn(u32), u32)>,
+    /// Last value written to MADCTL, reapplied by init
+    madctl: u8,
+    /// Last value written to COLMOD, reapplied by init
+    colmod: u8,
+    /// Last parameters written to PORCTRL, reapplied by init
+    porch: [u8; 5],
+    /// VRHS and VDVS values, see `set_vrh` and `set_vdv`
+    vrh: u8,
+    vdv: u8,
+    /// Per channel gains applied to colors before they are sent, see `set_color_correction`
+    color_correction: Option<[u16; 3]>,
+    /// FRCTRL2 value, see `configure_refresh`
+    frctrl2: u8,
+    /// Positive and negative gamma tables, see `set_gamma`
+    gamma_positive: [u8; 14],
+    gamma_negative: [u8; 14],
+    /// Normal or partial mode, see `current_mode`
+    mode: DisplayMode,
+    /// INVON (true) or INVOFF at init, see `invert_on_init`
+    invert: bool,
+    /// Extra attempts for a failed SPI write, see `set_write_retries`
+    write_retries: u8,
+    /// Out of sleep, see `state`
+    awake: bool,
+    /// Idle mode on, see `state`
+    idle: bool,
+    /// CS held low between `begin_batch` and `end_batch`
+    batching: bool,
+    /// Added to every row address, see `set_row_offset`
+    row_offset: u16,
+    /// Last CASET and RASET, see `debug_state`
+    columns: Option<(u16, u16)>,
+    rows: Option<(u16, u16)>,
+}
+
+/// CS marker for a panel whose chip select is tied low in hardware
+///
+/// A display built with `ST7789V::exclusive` has `NoCs` as its CS type, so the type
+/// states that the driver owns the SPI bus and never selects or deselects the panel.
+/// It is never constructed, the driver keeps no pin for it.
+pub struct NoCs<E>(PhantomData<E>);
+
+impl<E> OutputPin for NoCs<E> {
+    type Error = E;
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl<SPI, DC, RST, PinError, SpiError> ST7789V<SPI, NoCs<PinError>, DC, RST, PinError, SpiError>
+    where
+        SPI: spi::Write<u8, Error=SpiError>,
+        DC: OutputPin<Error=PinError>,
+        RST: OutputPin<Error=PinError>,
+{
+    /// Creates a display on an exclusive bus, CS tied low in hardware
+    ///
+    /// Same as `new`, with the CS type fixed to `NoCs`. For a shared bus use `with_cs`.
+    pub fn exclusive(spi: SPI, dc: DC, rst: RST, width: u16, height: u16) -> Self {
+        Self::new(spi, dc, rst, width, height)
+    }
 }
 
 impl<SPI, CS, DC, RST, PinError, SpiError> ST7789V<SPI, CS, DC, RST, PinError, SpiError>
@@ -353,19 +726,33 @@ impl<SPI, CS, DC, RST, PinError, SpiError> ST7789V<SPI, CS, DC, RST, PinError, S
         RST: OutputPin<Error=PinError>,
 {
     /// Creates a new display instance
+    ///
+    /// The bus has to run in SPI mode 0 (CPOL 0, CPHA 0), MSB first. The driver can not
+    /// set the mode, that is up to the HAL, and on a bus in another mode the panel sees
+    /// shifted bits without any error on the MCU side. With MISO wired,
+    /// `check_communication` catches that at runtime.
+    ///
+    /// Without a CS pin the driver never touches chip select and assumes it owns the
+    /// bus, with CS tied low in hardware. Do not use it on a bus shared with other
+    /// devices (e.g. through `SharedSpi`), their traffic would end up in the panel;
+    /// use `with_cs` there. `exclusive` spells the tied low intent out in the type.
+    ///
+    /// # Panics
+    ///
+    /// If `width` is larger than 240 or `height` larger than 320, the size of the frame
+    /// memory in portrait orientation. `with_cs` and `with_config` return
+    /// `Error::InvalidSize` instead.
     pub fn new(spi: SPI, dc: DC, rst: RST, width: u16, height: u16) -> Self {
-        ST7789V {
-            spi,
-            cfg: ST7789VConfig::new(dc, rst),
-            _pin_err: PhantomData,
-            _spi_err: PhantomData,
-            rotate: Rotate::Rotate0,
+        assert!(
+            fits_gram(width, height),
+            "ST7789V: {}x{} does not fit the 240x320 frame memory, pass the portrait size",
             width,
             height
-        }
+        );
+        Self::from_cfg(spi, ST7789VConfig::new(dc, rst), width, height)
     }
 
-    /// Creates a new display instance with chip select pin
+    /// Creates a new display instance with chip select pin, see `new` for the size limits
     pub fn with_cs(
         spi: SPI,
         mut cs: CS,
@@ -374,18 +761,13 @@ impl<SPI, CS, DC, RST, PinError, SpiError> ST7789V<SPI, CS, DC, RST, PinError, S
         width: u16,
         height: u16
     ) -> Result<Self, Error<PinError, SpiError>> {
-        cs.set_low().map_err(Error::Pin)?;
+        if !fits_gram(width, height) {
+            return Err(Error::InvalidSize);
+        }
+        // deselected until the first command, other devices may share the bus
+        cs.set_high().map_err(Error::Pin)?;
 
-        let cfg = ST7789VConfig::with_cs(cs, dc, rst);
-        Ok(ST7789V {
-            spi,
-            cfg,
-            _pin_err: PhantomData,
-            _spi_err: PhantomData,
-            rotate: Rotate::Rotate0,
-            width,
-            height,
-        })
+        Ok(Self::from_cfg(spi, ST7789VConfig::with_cs(cs, dc, rst), width, height))
     }
 
     /// Creates a new display instance using a previously build display config
@@ -395,22 +777,79 @@ impl<SPI, CS, DC, RST, PinError, SpiError> ST7789V<SPI, CS, DC, RST, PinError, S
         width: u16,
         height: u16
     ) -> Result<Self, Error<PinError, SpiError>> {
+        if !fits_gram(width, height) {
+            return Err(Error::InvalidSize);
+        }
         if let Some(cs) = cfg.cs.as_mut() {
-            cs.set_low().map_err(Error::Pin)?;
+            cs.set_high().map_err(Error::Pin)?;
+        }
+
+        Ok(Self::from_cfg(spi, cfg, width, height))
+    }
+
+    /// Creates a display from pins the application has set up already, CS included.
+    ///
+    /// Unlike `with_cs` no pin is touched here, CS stays at whatever level it has until
+    /// the first command, for pin management that owns the bring-up. `rotation` is applied
+    /// as with `set_rotate`, MADCTL going out with `init`. Size limits as in `with_cs`.
+    pub fn from_parts(
+        spi: SPI,
+        cs: CS,
+        dc: DC,
+        rst: RST,
+        width: u16,
+        height: u16,
+        rotation: Rotate,
+    ) -> Result<Self, Error<PinError, SpiError>> {
+        if !fits_gram(width, height) {
+            return Err(Error::InvalidSize);
         }
+        let mut display = Self::from_cfg(spi, ST7789VConfig::with_cs(cs, dc, rst), width, height);
+        display.rotate = rotation;
+        display.madctl = Self::rotated_madctl(display.madctl, rotation, Flip::None);
 
-        Ok(ST7789V {
+        Ok(display)
+    }
+
+    /// The driver with all remembered settings at their defaults, nothing sent
+    fn from_cfg(spi: SPI, cfg: ST7789VConfig<CS, DC, RST>, width: u16, height: u16) -> Self {
+        ST7789V {
             spi,
             cfg,
             _pin_err: PhantomData,
             _spi_err: PhantomData,
-            rotate: Rotate::Rotate0,
             width,
-            height
-        })
+            height,
+            rotate: Rotate::Rotate0,
+            flip: Flip::None,
+            ctrl_display: 0,
+            cabc: 0,
+            vcom: DEFAULT_VCOM,
+            cs_setup: None,
+            ramwr_settle: None,
+            madctl: DEFAULT_MADCTL,
+            colmod: DEFAULT_COLMOD,
+            porch: DEFAULT_PORCH,
+            vrh: DEFAULT_VRH,
+            vdv: DEFAULT_VDV,
+            color_correction: None,
+            frctrl2: DEFAULT_FRCTRL2,
+            gamma_positive: DEFAULT_GAMMA_POSITIVE,
+            gamma_negative: DEFAULT_GAMMA_NEGATIVE,
+            mode: DisplayMode::Normal,
+            invert: true,
+            write_retries: 0,
+            awake: false,
+            idle: false,
+            batching: false,
+            row_offset: 0,
+            columns: None,
+            rows: None,
+        }
     }
 
     /// Release the SPI bus and display config. This will also raise the chip select pin.
+    #[allow(clippy::type_complexity)]
     pub fn release(
         mut self,
     ) -> Result<(SPI, ST7789VConfig<CS, DC, RST>), Error<PinError, SpiError>> {
@@ -421,97 +860,179 @@ impl<SPI, CS, DC, RST, PinError, SpiError> ST7789V<SPI, CS, DC, RST, PinError, S
         Ok((self.spi, self.cfg))
     }
 
+    /// Panel resolution as passed at construction, `(width, height)`, regardless of
+    /// `set_rotate` and `set_flip`. Frame memory offsets are in these coordinates.
+    pub fn native_size(&self) -> (u16, u16) {
+        (self.width, self.height)
+    }
+
     /// Initialize the display
+    ///
+    /// Waits `INIT_DELAY_MS` in total, nearly all of the time init takes.
     pub fn init<DELAY>(&mut self, delay: &mut DELAY) -> Result<(), Error<PinError, SpiError>>
         where
             DELAY: DelayMs<u16>,
     {
-        self.hard_reset(delay)?
-            .command(Command::MADCTL, Some(&[0x00]))?
-            .command(Command::COLMOD, Some(&[0x05]))?
-            .command(Command::INVON, None)?
-            .command(Command::CASET, None)?
-            .data(&[0x00])?
-            .data(&[0x00])?
-            .data(&[0x01])?
-            .data(&[0x3f])?
-            .command(Command::RASET, None)?
-            .data(&[0x00])?
-            .data(&[0x00])?
-            .data(&[0x00])?
-            .data(&[0x33])?
-            .data(&[0x33])?
-            .command(Command::GCTRL, Some(&[0x35]))?
-            .command(Command::VCOMS, Some(&[0x1f]))?
-            .command(Command::LCMCTRL,Some(&[0x2c]))?
-            .command(Command::VDVVRHEN,Some(&[0x01]))?
-            .command(Command::VRHS,Some(&[0x12]))?
-            .command(Command::VDVS,Some(&[0x20]))?
-            .command(Command::FRCTRL2,Some(&[0x0f]))?
-            .command(Command::PWCTRL1,None)?
-            .data(&[0xa4])?
-            .data(&[0xa1])?
-            .command(Command::E0,None)?
-            .data(&[0xD0])?
-            .data(&[0x08])?
-            .data(&[0x11])?
-            .data(&[0x08])?
-            .data(&[0x0c])?
-            .data(&[0x15])?
-            .data(&[0x39])?
-            .data(&[0x33])?
-            .data(&[0x50])?
-            .data(&[0x36])?
-            .data(&[0x13])?
-            .data(&[0x14])?
-            .data(&[0x29])?
-            .data(&[0x2d])?
-            .command(Command::E1, None)?
-            .data(&[0xd0])?
-            .data(&[0x08])?
-            .data(&[0x10])?
-            .data(&[0x08])?
-            .data(&[0x06])?
-            .data(&[0x06])?
-            .data(&[0x39])?
-            .data(&[0x44])?
-            .data(&[0x51])?
-            .data(&[0x0b])?
-            .data(&[0x16])?
-            .data(&[0x14])?
-            .data(&[0x2f])?
-            .data(&[0x31])?
-            .command(Command::INVON, None)?
-            .command(Command::SLPOUT, None)?
-            .command(Command::DISPON, None)?;
+        self.hard_reset(delay)?;
+        self.init_no_reset(delay)
+    }
+
+    /// Initialize the display and fill the frame memory with `color` before turning the
+    /// display on, so the noise in uninitialized frame memory never shows.
+    pub fn init_and_clear<DELAY>(
+        &mut self,
+        color: u16,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<PinError, SpiError>>
+        where
+            DELAY: DelayMs<u16>,
+    {
+        self.hard_reset(delay)?;
+        self.run_init(|step| step.cmd != Command::DISPON, delay)?;
+        self.fill_screen(color)?;
+        self.display_on_delayed(delay)?;
+
         Ok(())
     }
 
-    pub fn set_rotate(&mut self, rotate: Rotate) -> Result<(), Error<PinError, SpiError>>{
-        // let w = self.width;
-        // let h = self.height;
-        // TODO change x, y  or do there
-        match rotate {
-            Rotate::Rotate270 => {
-                // self.memory_access_control(MemAccCtrlConfig::rotate_270())?;
-            }
-            Rotate::Rotate180 => {
-                // self.memory_access_control(MemAccCtrlConfig::rotate_180())?;
-            }
-            Rotate::Rotate90 => {
-                // self.memory_access_control(MemAccCtrlConfig::rotate_90())?;
-            }
-            _ => {
-                // self.memory_access_control(MemAccCtrlConfig::rotate_0())?;
-            }
+    /// Initialize the display without pulsing the reset pin.
+    ///
+    /// Use this when RST is shared with other chips and the panel has already been reset
+    /// externally, or after a `soft_reset`. Like `reinit_registers` it uses the MADCTL,
+    /// COLMOD, porch and VCOM values set since construction.
+    pub fn init_no_reset<DELAY>(
+        &mut self,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<PinError, SpiError>>
+        where
+            DELAY: DelayMs<u16>,
+    {
+        self.run_init(|_| true, delay)
+    }
+
+    /// Re-apply the register configuration after a `soft_reset`, keeping frame memory.
+    ///
+    /// Sends the register writes of `DEFAULT_INIT` (gamma, power, gate and so on) with the
+    /// values last set through `memory_access_control`, `color_mode`, `set_porch`,
+    /// `set_vcom`, `set_vrh`, `set_vdv`, `set_gamma` and the inversion setters, but not
+    /// SLPOUT and DISPON.
+    /// SWRESET leaves the panel in sleep with the display off, so follow this with
+    /// `sleep_out` and `display_on` to show the frame memory as it was.
+    pub fn reinit_registers<DELAY>(
+        &mut self,
+        delay: &mut DELAY,
+    ) -> Result<&mut Self, Error<PinError, SpiError>>
+        where
+            DELAY: DelayMs<u16>,
+    {
+        self.run_init(|step| !matches!(step.cmd, Command::SLPOUT | Command::DISPON), delay)?;
+
+        Ok(self)
+    }
+
+    /// Run the steps of `DEFAULT_INIT` that `filter` accepts, with MADCTL, COLMOD, PORCTRL,
+    /// VCOMS, FRCTRL2, VRHS/VDVS, the gamma tables and INVON replaced by the values the driver
+    /// remembers.
+    fn run_init<DELAY, F>(
+        &mut self,
+        filter: F,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<PinError, SpiError>>
+        where
+            DELAY: DelayMs<u16>,
+            F: Fn(&InitStep) -> bool,
+    {
+        for step in DEFAULT_INIT.iter().filter(|step| filter(step)) {
+            match step.cmd {
+                Command::MADCTL => self.memory_access_control_value(self.madctl)?,
+                Command::COLMOD => self.command(Command::COLMOD, Some(&[self.colmod]))?,
+                Command::PORCTRL => {
+                    let porch = self.porch;
+                    self.command(Command::PORCTRL, Some(&porch))?
+                }
+                Command::VCOMS => self.command(Command::VCOMS, Some(&[self.vcom]))?,
+                Command::RASET if self.row_offset != 0 => self.row_address(0, self.height)?,
+                Command::FRCTRL2 => self.command(Command::FRCTRL2, Some(&[self.frctrl2]))?,
+                Command::E0 => {
+                    let gamma = self.gamma_positive;
+                    self.command(Command::E0, Some(&gamma))?
+                }
+                Command::E1 => {
+                    let gamma = self.gamma_negative;
+                    self.command(Command::E1, Some(&gamma))?
+                }
+                Command::VDVVRHEN => self.power_voltages()?,
+                // sent by power_voltages together with the enable
+                Command::VRHS | Command::VDVS => self,
+                Command::INVON if !self.invert => self.command(Command::INVOFF, None)?,
+                Command::DISPON => self.display_on_delayed(delay)?,
+                _ => self.run_sequence(core::slice::from_ref(step), delay)?,
+            };
         }
+
+        Ok(())
+    }
+
+    /// Full power up sequence: reset, init (sleep out, display on), then backlight on.
+    ///
+    /// The backlight pin is switched last so the noise in uninitialized frame memory is
+    /// never visible. It is passed in rather than owned since many boards drive it from a
+    /// PWM channel or share it with other peripherals.
+    pub fn power_on<DELAY, BL>(
+        &mut self,
+        delay: &mut DELAY,
+        backlight: &mut BL,
+    ) -> Result<(), Error<PinError, SpiError>>
+        where
+            DELAY: DelayMs<u16>,
+            BL: OutputPin<Error=PinError>,
+    {
+        self.init(delay)?;
+        backlight.set_high().map_err(Error::Pin)?;
+
+        Ok(())
+    }
+
+    /// Reverse of `power_on`: backlight off, display off, then sleep in.
+    pub fn power_off<DELAY, BL>(
+        &mut self,
+        delay: &mut DELAY,
+        backlight: &mut BL,
+    ) -> Result<(), Error<PinError, SpiError>>
+        where
+            DELAY: DelayMs<u16>,
+            BL: OutputPin<Error=PinError>,
+    {
+        backlight.set_low().map_err(Error::Pin)?;
+        self.display_off()?.sleep_in(delay)?;
+
+        Ok(())
+    }
+
+    /// Sets the rotation by writing the MY/MX bits of `madctl_for(rotate)` to MADCTL.
+    ///
+    /// The panel picks the corner frame memory starts in as drawn above
+    /// `MemAccCtrlConfig::rotate_0`, coordinates and pixel order stay as they are. No axes
+    /// are swapped, width and height remain as constructed. A `set_flip` mirror is kept on
+    /// top, the other MADCTL bits are left alone.
+    pub fn set_rotate(&mut self, rotate: Rotate) -> Result<(), Error<PinError, SpiError>>{
+        let value = Self::rotated_madctl(self.madctl, rotate, self.flip);
         self.rotate = rotate;
+        self.memory_access_control_value(value)?;
 
         Ok(())
     }
 
+    /// `madctl` with MY/MX set for `rotate`, mirrored further by `flip`
+    fn rotated_madctl(madctl: u8, rotate: Rotate, flip: Flip) -> u8 {
+        (madctl & !Flip::Both.value()) | (madctl_for(rotate) ^ flip.value())
+    }
+
 
     /// This sets the RGB interface and control interface color format.
+    ///
+    /// Drawing keeps taking RGB565 colors, encoded for the format by `Bits16`, `Bits12`
+    /// (RGB444 packed two pixels to three bytes) or `Bits18`.
     pub fn color_mode<DELAY>(
         &mut self,
         color_format: ColorFormat,
@@ -520,7 +1041,8 @@ impl<SPI, CS, DC, RST, PinError, SpiError> ST7789V<SPI, CS, DC, RST, PinError, S
         where
             DELAY: DelayMs<u16>,
     {
-        self.command(Command::COLMOD, Some(&[color_format.value()]))?;
+        self.colmod = color_format.value();
+        self.command(Command::COLMOD, Some(&[self.colmod]))?;
         delay.delay_ms(10);
 
         Ok(self)
@@ -528,11 +1050,76 @@ impl<SPI, CS, DC, RST, PinError, SpiError> ST7789V<SPI, CS, DC, RST, PinError, S
 
     /// This sets the porch setting.
     pub fn porch_setting(&mut self) -> Result<&mut Self, Error<PinError, SpiError>> {
-        self.command(Command::PORCTRL, Some(&[0x0C, 0x0C, 0x00, 0x33, 0x33]))?;
+        let [back, front, _, idle, partial] = DEFAULT_PORCH;
+        self.set_porch(back, front, false, idle, partial)
+    }
+
+    /// Porch setting (PORCTRL).
+    ///
+    /// * `back_porch` - BPA, back porch in normal mode (0x01 - 0x7F lines)
+    /// * `front_porch` - FPA, front porch in normal mode (0x01 - 0x7F lines)
+    /// * `separate_enable` - PSEN, use `idle` and `partial` for those modes instead of the
+    ///   normal mode porches
+    /// * `idle` - FPB in the high and BPB in the low nibble, porches in idle mode
+    /// * `partial` - FPC in the high and BPC in the low nibble, porches in partial mode
+    ///
+    /// The porches add to the 320 lines of every frame, so together with RTNA in FRCTRL2
+    /// they set the frame rate in normal mode:
+    /// `10 MHz / ((250 + RTNA * 16) * (320 + FPA + BPA))`.
+    pub fn set_porch(
+        &mut self,
+        back_porch: u8,
+        front_porch: u8,
+        separate_enable: bool,
+        idle: u8,
+        partial: u8,
+    ) -> Result<&mut Self, Error<PinError, SpiError>> {
+        self.porch = [
+            back_porch & 0x7F,
+            front_porch & 0x7F,
+            separate_enable as u8,
+            idle,
+            partial,
+        ];
+        let porch = self.porch;
+        self.command(Command::PORCTRL, Some(&porch))?;
 
         Ok(self)
     }
 
+    /// Pick RTNA and the normal mode porches for a frame rate close to `target_hz` and
+    /// write them (FRCTRL2 and PORCTRL), kept for `init`.
+    ///
+    /// Uses the formula of `set_porch`, which spans about 23 to 124 Hz; targets outside
+    /// get the nearest end. For every RTNA the porch closest to the target is computed and
+    /// the best pair wins, on a tie the one with the shortest line period. The returned
+    /// values are what was written, with the frame rate they give.
+    pub fn configure_refresh(&mut self, target_hz: u16) -> Result<RefreshConfig, Error<PinError, SpiError>> {
+        let target = target_hz as u32 * 1000;
+        let candidate = |rtna: u8| {
+            let lines = 10_000_000_000u64 / ((250 + rtna as u64 * 16) * target.max(1) as u64);
+            let porch = lines.saturating_sub(320).clamp(2, 254) as u16;
+            (rtna, porch, frame_rate_mhz(rtna, porch))
+        };
+        let mut best = candidate(0);
+        for rtna in 1..32 {
+            let next = candidate(rtna);
+            if next.2.abs_diff(target) < best.2.abs_diff(target) {
+                best = next;
+            }
+        }
+        let (rtna, porch, millihertz) = best;
+
+        let (back_porch, front_porch) = ((porch / 2) as u8, (porch - porch / 2) as u8);
+        let [_, _, separate, idle, partial] = self.porch;
+        self.set_porch(back_porch, front_porch, separate != 0, idle, partial)?;
+        self.frctrl2 = (self.frctrl2 & 0b1110_0000) | rtna;
+        let frctrl2 = self.frctrl2;
+        self.command(Command::FRCTRL2, Some(&[frctrl2]))?;
+
+        Ok(RefreshConfig { rtna, back_porch, front_porch, millihertz })
+    }
+
     /// This sets the gate control.
     pub fn gate_control(&mut self) -> Result<&mut Self, Error<PinError, SpiError>> {
         self.command(Command::GCTRL, Some(&[0x35]))?;
@@ -540,6 +1127,27 @@ impl<SPI, CS, DC, RST, PinError, SpiError> ST7789V<SPI, CS, DC, RST, PinError, S
         Ok(self)
     }
 
+    /// Gate control (GATECTRL).
+    ///
+    /// * `ngate` - NL, number of gate lines as `(ngate + 1) * 8`, e.g. 0x27 for 320 lines
+    ///   and 0x1D for 240 lines.
+    /// * `first_gate` - SCN, first scan line (gate) to drive.
+    /// * `flags` - GATECTRL_* bits: scan direction (GS), interlace (SM) and mirror
+    ///   gate timing (TMG).
+    pub fn set_gate_control(
+        &mut self,
+        ngate: u8,
+        first_gate: u8,
+        flags: u8,
+    ) -> Result<&mut Self, Error<PinError, SpiError>> {
+        self.command(
+            Command::GATECTRL,
+            Some(&[ngate & 0x3F, first_gate & 0x3F, flags & 0x15]),
+        )?;
+
+        Ok(self)
+    }
+
     /// This sets the VCOMS setting.
     pub fn vcoms_setting(&mut self) -> Result<&mut Self, Error<PinError, SpiError>> {
         self.command(Command::VCOMS, Some(&[0x35]))?;
@@ -548,38 +1156,227 @@ impl<SPI, CS, DC, RST, PinError, SpiError> ST7789V<SPI, CS, DC, RST, PinError, S
     }
 
     /// This sets the LCM control.
+    ///
+    /// Also writes the power voltages and frame rate the driver keeps (VDVVRHEN, VRHS,
+    /// VDVS, FRCTRL2), the same values `init` sends. Unless changed with `set_vrh` that
+    /// makes VRHS 0x12, where this used to send 0x13.
     pub fn lcm_control(&mut self) -> Result<&mut Self, Error<PinError, SpiError>> {
-        self.command(Command::LCMCTRL, Some(&[0x2C]))?;
+        self.command(Command::LCMCTRL, Some(&[DEFAULT_LCMCTRL]))?;
 
-        self.command(Command::VDVVRHEN, Some(&[0x01]))?;
-        self.command(Command::VRHS, Some(&[0x13]))?;
-        self.command(Command::VDVS, Some(&[0x20]))?;
-        self.command(Command::FRCTRL2, Some(&[0x0F]))?;
-        self.command(Command::PWCTRL1, Some(&[0xA4, 0xA1]))?;
+        self.power_voltages()?;
+        let frctrl2 = self.frctrl2;
+        self.command(Command::FRCTRL2, Some(&[frctrl2]))?;
+        self.command(Command::PWCTRL1, Some(&DEFAULT_PWCTRL1))?;
         self.command(Command::UNKNOWN_D6, Some(&[0xA1]))?;
 
         Ok(self)
     }
 
-    /// This will put the LCD module into minimum power consumption mode.
+    /// Scale red, green and blue of every color drawn by `r_gain`, `g_gain` and `b_gain`,
+    /// in 1/256 (256 leaves a channel as it is, results are clamped to the channel
+    /// maximum). For matching the tint of panels side by side, also without gamma access.
     ///
-    /// In this mode the DC/DC converter is stopped, the internal oscillator and the panel
-    /// scanning is stopped. The MCU interface and memory are still working and the memory
-    /// keeps its contents.
-    pub fn sleep_in<DELAY>(
+    /// Done in software on colors passed as `u16` or `Rgb565` (`pixels`, fills, drawing),
+    /// raw byte buffers like `mem_write` or `write_framebuffer` are sent untouched. All
+    /// gains at 256 turn it off again, with no cost left on the drawing paths.
+    pub fn set_color_correction(&mut self, r_gain: u16, g_gain: u16, b_gain: u16) -> &mut Self {
+        self.color_correction = match [r_gain, g_gain, b_gain] {
+            [256, 256, 256] => None,
+            gains => Some(gains),
+        };
+        self
+    }
+
+    /// Write the positive (E0) and negative (E1) voltage gamma tables, kept for `init`.
+    ///
+    /// `DEFAULT_GAMMA_POSITIVE` and `DEFAULT_GAMMA_NEGATIVE` are the tables `init` writes
+    /// unless changed here.
+    pub fn set_gamma(
         &mut self,
-        delay: &mut DELAY,
-    ) -> Result<&mut Self, Error<PinError, SpiError>>
-        where
-            DELAY: DelayMs<u16>,
-    {
-        self.command(Command::SLPIN, None)?;
-        delay.delay_ms(5);
+        positive: &[u8; 14],
+        negative: &[u8; 14],
+    ) -> Result<&mut Self, Error<PinError, SpiError>> {
+        self.gamma_positive = *positive;
+        self.gamma_negative = *negative;
+        self.command(Command::E0, Some(positive))?
+            .command(Command::E1, Some(negative))?;
 
         Ok(self)
     }
 
-    /// In this mode the DC/DC converter is enabled, internal display oscillator and the panel
+    /// Turn the digital gamma look-up tables on or off (DGMEN).
+    ///
+    /// The tables for red (DGMLUTR, 0xE2) and blue (DGMLUTB, 0xE3), 64 bytes each, are
+    /// only applied while this is on, writing them alone shows no change. Write them with
+    /// `transaction` and then enable them here. Off after reset.
+    pub fn enable_digital_gamma(&mut self, on: bool) -> Result<&mut Self, Error<PinError, SpiError>> {
+        let dgmen = if on { 0b0000_0100 } else { 0 };
+        self.command(Command::DGMEN, Some(&[dgmen]))?;
+
+        Ok(self)
+    }
+
+    /// Unlock the extended command set 2 registers.
+    ///
+    /// Sets EN in CMD2EN (0xDF) and then sends 0xFE, 0xEF. A genuine ST7789V comes out of
+    /// reset with command 2 enabled and ignores 0xFE/0xEF, so this is only needed on
+    /// compatible clone controllers that expect the 0xFE/0xEF pair before gamma (E0/E1,
+    /// written by `init`, and the digital gamma tables E2/E3) or power registers take
+    /// effect. Symptom: gamma or power writes seem to do nothing. Call it before `init`.
+    pub fn enable_command_2(&mut self) -> Result<&mut Self, Error<PinError, SpiError>> {
+        self.command(Command::CMD2EN, Some(&[0x5A, 0x69, 0x02, 0x01]))?
+            .command(Command::INREGEN1, None)?
+            .command(Command::INREGEN2, None)?;
+
+        Ok(self)
+    }
+
+    /// Program one byte of the panel's NV memory: NVMSET with `addr` and `data`, then the
+    /// program action (PROMACT 0x29 0xA5), then a 100 ms wait for the write to finish.
+    ///
+    /// The addresses (ID1/ID2/ID3 among them, readable with `read_id_bytes`) are in the
+    /// NVM section of the datasheet. NV memory can only be written a few times and the
+    /// write usually needs the panel's programming supply, so this is meant for stamping
+    /// a serial or batch number once in production. Other controllers program NVM through
+    /// 0xD9/0xDE instead, those commands do nothing on an ST7789V.
+    pub fn nvm_write<DELAY>(
+        &mut self,
+        addr: u8,
+        data: u8,
+        delay: &mut DELAY,
+    ) -> Result<&mut Self, Error<PinError, SpiError>>
+        where
+            DELAY: DelayMs<u16>,
+    {
+        self.command(Command::NVMSET, Some(&[addr, data]))?
+            .command(Command::PROMACT, Some(&[0x29, 0xA5]))?;
+        delay.delay_ms(100);
+
+        Ok(self)
+    }
+
+    /// Lock command set 2 again by clearing EN in CMD2EN. Clone controllers unlocked with
+    /// 0xFE/0xEF have no lock command and stay unlocked until reset.
+    pub fn disable_command_2(&mut self) -> Result<&mut Self, Error<PinError, SpiError>> {
+        self.command(Command::CMD2EN, Some(&[0x5A, 0x69, 0x02, 0x00]))?;
+
+        Ok(self)
+    }
+
+    /// Set the VRH voltage (VRHS), this shifts the gamma reference and so the contrast.
+    ///
+    /// Valid values are 0x00 (3.55 V) to 0x27 (5.5 V) in 0.05 V steps, larger values are
+    /// clamped. The reset default is 0x0B (4.1 V), `init` uses 0x12 and most panels look
+    /// right between 0x10 and 0x14. The value is kept for later calls to `init` and
+    /// `lcm_control`.
+    pub fn set_vrh(&mut self, value: u8) -> Result<&mut Self, Error<PinError, SpiError>> {
+        self.vrh = value.min(0x27);
+        self.power_voltages()
+    }
+
+    /// VDVVRHEN, then VRHS and VDVS with the remembered values. The one place the power
+    /// voltages are written, for `init`, `lcm_control`, `set_vrh` and `set_vdv` alike.
+    fn power_voltages(&mut self) -> Result<&mut Self, Error<PinError, SpiError>> {
+        let (vrh, vdv) = (self.vrh, self.vdv);
+        self.command(Command::VDVVRHEN, Some(&[0x01]))?
+            .command(Command::VRHS, Some(&[vrh]))?
+            .command(Command::VDVS, Some(&[vdv]))
+    }
+
+    /// Retry a failed SPI write up to `n` more times before returning the error, for noisy
+    /// buses where single transfers fail now and then. Off (0) by default.
+    ///
+    /// A write is retried as a whole with CS and DC unchanged, the panel sees the bytes
+    /// again as if the failed transfer never happened. This only helps when the bus
+    /// reports the failure before clocking anything out, a persistent failure still
+    /// surfaces after the last attempt.
+    pub fn set_write_retries(&mut self, n: u8) -> &mut Self {
+        self.write_retries = n;
+        self
+    }
+
+    /// Wait `us` microseconds after asserting CS before clocking out data, for boards with
+    /// slow level shifters on CS. `delay_us` does the waiting, typically the HAL's busy wait
+    /// (a plain `fn` since the driver does not own a delay). 0 turns the delay off again.
+    pub fn set_cs_setup_delay_us(&mut self, us: u32, delay_us: fn(u32)) -> &mut Self {
+        self.cs_setup = if us == 0 { None } else { Some((delay_us, us)) };
+        self
+    }
+
+    /// Wait `us` microseconds between the RAMWR (or RAMWRC) command byte and the pixel
+    /// data, for slow clone panels that drop the first pixels otherwise. Applies to every
+    /// memory write, `address_window`, `mem_write`, `pixels` and the drawing calls alike.
+    /// `delay_us` works as in `set_cs_setup_delay_us`, 0 (the default) turns it off.
+    pub fn set_ramwr_settle_us(&mut self, us: u32, delay_us: fn(u32)) -> &mut Self {
+        self.ramwr_settle = if us == 0 { None } else { Some((delay_us, us)) };
+        self
+    }
+
+    /// Assert CS once and keep it low for all commands and data until `end_batch`,
+    /// instead of toggling it around every command. Speeds up frames made of many small
+    /// draws. DC alone separates commands from data, so the panel does not need the CS
+    /// edges.
+    ///
+    /// The bus stays claimed for the whole batch, do not talk to other devices on it in
+    /// between. A reset or `shutdown` ends the batch.
+    pub fn begin_batch(&mut self) -> Result<&mut Self, Error<PinError, SpiError>> {
+        self.select()?;
+        self.batching = true;
+
+        Ok(self)
+    }
+
+    /// Raise CS again after `begin_batch`.
+    pub fn end_batch(&mut self) -> Result<&mut Self, Error<PinError, SpiError>> {
+        self.batching = false;
+        self.deselect()?;
+
+        Ok(self)
+    }
+
+    /// Set the VCOM voltage (VCOMS) and use it in later calls to `init`.
+    ///
+    /// Valid values are 0x00 (0.1 V) to 0x3F (1.675 V) in 25 mV steps, larger values are
+    /// clamped. The reset default is 0x20 (0.9 V) and `init` uses 0x1F (0.875 V). Most
+    /// modules want something between 0x19 and 0x35, step through that range on a gray
+    /// test image and keep the value with the least flicker.
+    pub fn set_vcom(&mut self, value: u8) -> Result<&mut Self, Error<PinError, SpiError>> {
+        self.vcom = value.min(0x3F);
+        let vcom = self.vcom;
+        self.command(Command::VCOMS, Some(&[vcom]))?;
+
+        Ok(self)
+    }
+
+    /// Set the VDV voltage (VDVS), a fine offset on top of VRH.
+    ///
+    /// Valid values are 0x00 (-0.8 V) to 0x3F (+0.775 V) in 25 mV steps, larger values
+    /// are clamped. 0x20 is 0 V, the reset default and what `init` uses. The value is
+    /// kept for later calls to `init` and `lcm_control`.
+    pub fn set_vdv(&mut self, value: u8) -> Result<&mut Self, Error<PinError, SpiError>> {
+        self.vdv = value.min(0x3F);
+        self.power_voltages()
+    }
+
+    /// This will put the LCD module into minimum power consumption mode.
+    ///
+    /// In this mode the DC/DC converter is stopped, the internal oscillator and the panel
+    /// scanning is stopped. The MCU interface and memory are still working and the memory
+    /// keeps its contents.
+    pub fn sleep_in<DELAY>(
+        &mut self,
+        delay: &mut DELAY,
+    ) -> Result<&mut Self, Error<PinError, SpiError>>
+        where
+            DELAY: DelayMs<u16>,
+    {
+        self.command(Command::SLPIN, None)?;
+        delay.delay_ms(5);
+
+        Ok(self)
+    }
+
+    /// In this mode the DC/DC converter is enabled, internal display oscillator and the panel
     /// scanning is started.
     pub fn sleep_out<DELAY>(
         &mut self,
@@ -599,6 +1396,7 @@ impl<SPI, CS, DC, RST, PinError, SpiError> ST7789V<SPI, CS, DC, RST, PinError, S
         &mut self,
     ) -> Result<&mut Self, Error<PinError, SpiError>> {
         self.command(Command::PTLON, None)?;
+        self.mode = DisplayMode::Partial;
 
         Ok(self)
     }
@@ -606,24 +1404,101 @@ impl<SPI, CS, DC, RST, PinError, SpiError> ST7789V<SPI, CS, DC, RST, PinError, S
     /// Leave partial mode and enter normal mode.
     pub fn normal_mode(&mut self) -> Result<&mut Self, Error<PinError, SpiError>> {
         self.command(Command::NORON, None)?;
+        self.mode = DisplayMode::Normal;
+
+        Ok(self)
+    }
+
+    /// Whether the display is in normal or partial mode, as last set by `normal_mode`,
+    /// `partial_display_mode` or a reset. Raw writes through `transaction` are not seen.
+    pub fn current_mode(&self) -> DisplayMode {
+        self.mode
+    }
+
+    /// Everything the driver knows about the panel state in one place: sleep, inversion,
+    /// idle and display mode, rotation and color format.
+    ///
+    /// This is tracked from the commands the driver sent, nothing is read back. Raw writes
+    /// through `transaction` are not seen, and after a reset everything but rotation and
+    /// color format is back at its reset value until `init` runs again.
+    pub fn state(&self) -> DriverState {
+        DriverState {
+            awake: self.awake,
+            inverted: self.invert,
+            idle: self.idle,
+            mode: self.mode,
+            rotation: self.rotate,
+            color_format: PixelFormat::from_value(self.colmod),
+        }
+    }
+
+    /// The address window and MADCTL the driver last sent, for finding out why a draw
+    /// lands in the wrong place.
+    ///
+    /// The window is in frame memory coordinates, after rotation, as the panel sees it.
+    /// Like `state` this is tracked, not read back. With the `log` feature the CASET,
+    /// RASET and MADCTL writes are traced as they happen.
+    pub fn debug_state(&self) -> WindowDebug {
+        WindowDebug {
+            columns: self.columns,
+            rows: self.rows,
+            madctl: self.madctl,
+            rotation: self.rotate,
+        }
+    }
+
+    /// All pixels off (0x22): the panel shows black regardless of frame memory, unlike
+    /// `display_off` the panel keeps being driven. Useful for checking backlight uniformity.
+    ///
+    /// The command comes from MIPI DCS and is not listed in the ST7789V datasheet, most
+    /// panels accept it but some ignore it. Leave the mode with `normal_display`.
+    pub fn all_pixels_off(&mut self) -> Result<&mut Self, Error<PinError, SpiError>> {
+        self.command(Command::ALLPOFF, None)?;
+
+        Ok(self)
+    }
+
+    /// All pixels on (0x23): the panel shows white regardless of frame memory, see
+    /// `all_pixels_off` for the caveats.
+    pub fn all_pixels_on(&mut self) -> Result<&mut Self, Error<PinError, SpiError>> {
+        self.command(Command::ALLPON, None)?;
 
         Ok(self)
     }
 
-    /// Display Inversion Off
+    /// Show frame memory again after `all_pixels_on`/`all_pixels_off`, same as `normal_mode`.
+    pub fn normal_display(&mut self) -> Result<&mut Self, Error<PinError, SpiError>> {
+        self.normal_mode()
+    }
+
+    /// Display Inversion Off, also used by later calls to `init`
     pub fn inversion_off(&mut self) -> Result<&mut Self, Error<PinError, SpiError>> {
+        self.invert = false;
         self.command(Command::INVOFF, None)?;
 
         Ok(self)
     }
 
-    /// Display Inversion On
+    /// Display Inversion On, also used by later calls to `init`
     pub fn inversion_on(&mut self) -> Result<&mut Self, Error<PinError, SpiError>> {
+        self.invert = true;
         self.command(Command::INVON, None)?;
 
         Ok(self)
     }
 
+    /// Choose whether `init` turns display inversion on (INVON) or off (INVOFF), without
+    /// sending anything now. The default is on.
+    ///
+    /// IPS panels are built to be driven inverted and show negative colors without INVON,
+    /// this includes the Waveshare 2inch module and most 1.3"/1.54"/2" IPS breakouts. TN
+    /// panels (many 2.4"/2.8" modules) want inversion off. If black shows as white after
+    /// init, flip this.
+    pub fn invert_on_init(&mut self, invert: bool) -> &mut Self {
+        self.invert = invert;
+        self
+    }
+
     /// The LCD enters DISPLAY OFF mode. In this mode, the output from frame memory is
     /// disabled and a blank page is inserted. This command does not change to the frame
     /// memory contents nor any other status. There will be no abnormal visible effect on the
@@ -634,6 +1509,42 @@ impl<SPI, CS, DC, RST, PinError, SpiError> ST7789V<SPI, CS, DC, RST, PinError, S
         Ok(self)
     }
 
+    /// `display_off` followed by a 20 ms wait, a frame at 60 Hz, so the blank page is on
+    /// the glass before a following SLPIN or backlight change.
+    pub fn display_off_delayed<DELAY>(
+        &mut self,
+        delay: &mut DELAY,
+    ) -> Result<&mut Self, Error<PinError, SpiError>>
+        where
+            DELAY: DelayMs<u16>,
+    {
+        self.display_off()?;
+        delay.delay_ms(20);
+
+        Ok(self)
+    }
+
+    /// Send `cmd` with optional parameters, for registers the driver has no method for.
+    ///
+    /// The values the driver remembers for `init` (MADCTL, COLMOD, porches, voltages,
+    /// gamma, ...) are not updated by commands sent here, use the setters for those.
+    /// Sleep and idle state are tracked as with every command. For a sequence with CS held
+    /// low throughout see `transaction`.
+    pub fn send(&mut self, cmd: Command, params: Option<&[u8]>) -> Result<&mut Self, Error<PinError, SpiError>> {
+        self.command(cmd, params)
+    }
+
+    /// Check the write path without initializing: sends a NOP, toggling CS and DC.
+    ///
+    /// The panel ignores NOP, so this is safe at any time. Without read support it can
+    /// only tell that the SPI and pin calls succeed, `ping_read` also checks that the
+    /// panel answers.
+    pub fn ping(&mut self) -> Result<(), Error<PinError, SpiError>> {
+        self.command(Command::NOP, None)?;
+
+        Ok(())
+    }
+
     /// The LCD enters DISPLAY ON mode. The output from the frame memory is enabled. This
     /// command does not change the frame memory content nor any other status.
     pub fn display_on(&mut self) -> Result<&mut Self, Error<PinError, SpiError>> {
@@ -642,16 +1553,150 @@ impl<SPI, CS, DC, RST, PinError, SpiError> ST7789V<SPI, CS, DC, RST, PinError, S
         Ok(self)
     }
 
+    /// `display_on` after waiting the 120 ms the panel needs after SLPOUT to settle, so the
+    /// first frame shows stable output. `init` turns the display on this way.
+    pub fn display_on_delayed<DELAY>(
+        &mut self,
+        delay: &mut DELAY,
+    ) -> Result<&mut Self, Error<PinError, SpiError>>
+        where
+            DELAY: DelayMs<u16>,
+    {
+        delay.delay_ms(120);
+        self.display_on()
+    }
+
+    /// Write CTRL display (WRCTRLD).
+    ///
+    /// * `brightness_ctrl` - BCTRL (D5), brightness control block on. When off the
+    ///   display brightness registers are ignored.
+    /// * `dimming` - DD (D3), display dimming. Brightness changes fade over a few frames
+    ///   instead of jumping. Only effective when BCTRL is on.
+    /// * `backlight` - BL (D2), backlight control on. When off the backlight PWM output
+    ///   is held inactive.
+    pub fn write_ctrl_display(
+        &mut self,
+        brightness_ctrl: bool,
+        dimming: bool,
+        backlight: bool,
+    ) -> Result<&mut Self, Error<PinError, SpiError>> {
+        let mut value = 0;
+        if brightness_ctrl {
+            value |= WRCTRLD_BCTRL;
+        }
+        if dimming {
+            value |= WRCTRLD_DD;
+        }
+        if backlight {
+            value |= WRCTRLD_BL;
+        }
+        self.command(Command::WRCTRLD, Some(&[value]))?;
+        self.ctrl_display = value;
+
+        Ok(self)
+    }
+
+    /// Turn display dimming (smooth brightness transitions) on or off.
+    ///
+    /// This sets BCTRL and DD in WRCTRLD together and keeps the BL bit as last written.
+    pub fn enable_display_dimming(&mut self, on: bool) -> Result<&mut Self, Error<PinError, SpiError>> {
+        let backlight = self.ctrl_display & WRCTRLD_BL != 0;
+        let brightness_ctrl = on || self.ctrl_display & WRCTRLD_BCTRL != 0;
+        self.write_ctrl_display(brightness_ctrl, on, backlight)
+    }
+
+    /// Write content adaptive brightness control and color enhancement (WRCACE).
+    ///
+    /// * D7 - CECTRL, color enhancement on
+    /// * D5..D4 - CE, color enhancement level (low/medium/high)
+    /// * D1..D0 - C, CABC mode (off/user interface/still picture/moving image)
+    pub fn write_cabc(&mut self, value: u8) -> Result<&mut Self, Error<PinError, SpiError>> {
+        self.command(Command::WRCACE, Some(&[value]))?;
+        self.cabc = value;
+
+        Ok(self)
+    }
+
+    /// Select the content adaptive brightness control mode.
+    ///
+    /// The color enhancement bits of WRCACE are kept as last written. Use
+    /// `cabc_min_brightness` to limit how far CABC may dim the backlight.
+    pub fn set_cabc(&mut self, mode: CabcMode) -> Result<&mut Self, Error<PinError, SpiError>> {
+        self.write_cabc((self.cabc & !0b11) | mode.value())
+    }
+
+    /// Set the color enhancement in WRCACE: 0 off, 1 low, 2 medium, 3 high (larger values
+    /// are clamped), for more saturated colors without touching gamma.
+    ///
+    /// Color enhancement shares WRCACE with the CABC mode, the CABC bits are kept as last
+    /// written by `set_cabc` or `write_cabc`, and `set_cabc` keeps these in turn. Unlike
+    /// CABC it works on the pixel data, so it also shows on modules without LEDPWM.
+    pub fn set_color_enhancement(&mut self, level: u8) -> Result<&mut Self, Error<PinError, SpiError>> {
+        // CECTRL on (D7) with CE in D5..D4: low 00, medium 01, high 11
+        let ce = match level {
+            0 => 0,
+            1 => 0b1000_0000,
+            2 => 0b1001_0000,
+            _ => 0b1011_0000,
+        };
+        self.write_cabc((self.cabc & 0b0000_0011) | ce)
+    }
+
+    /// Write CABC minimum brightness (WRCABCMB).
+    ///
+    /// CABC will never dim the backlight below this value (0x00 - 0xFF).
+    pub fn cabc_min_brightness(&mut self, value: u8) -> Result<&mut Self, Error<PinError, SpiError>> {
+        self.command(Command::WRCABCMB, Some(&[value]))?;
+
+        Ok(self)
+    }
+
     /// Define read/write scanning direction of the frame memory.
     pub fn memory_access_control(
         &mut self,
         _config: MemAccCtrlConfig,
     ) -> Result<&mut Self, Error<PinError, SpiError>> {
-        self.command(Command::MADCTL, Some(&[_config.value()]))?;
+        self.memory_access_control_value(_config.value())
+    }
+
+    /// Mirror the panel horizontally and/or vertically on top of `set_rotate`.
+    ///
+    /// Toggles MX/MY in MADCTL against the bits of the current rotation, the panel mirrors
+    /// while scanning out so drawing coordinates stay the same, e.g. `Flip::Horizontal` for
+    /// a module seen through a mirror. The bits overwrite MX/MY from
+    /// `memory_access_control`, so call this after it; `init` keeps the setting. On panels
+    /// smaller than the 240x320 frame memory a vertical flip also moves the visible part of
+    /// frame memory, see `set_row_offset`.
+    pub fn set_flip(&mut self, flip: Flip) -> Result<&mut Self, Error<PinError, SpiError>> {
+        let value = Self::rotated_madctl(self.madctl, self.rotate, flip);
+        self.flip = flip;
+        self.memory_access_control_value(value)
+    }
+
+    /// Shift every row address by `rows`, for panels showing only part of the 320 rows
+    /// of frame memory.
+    ///
+    /// 0 (the default) suits a 240x240 module in its native orientation. With MY set in
+    /// MADCTL, by `set_flip` or by `Rotate90` and `Rotate180` in `set_rotate`, the same
+    /// module shows the last 240 rows, use `ROW_OFFSET_240X240` then. Returns `InvalidRowAddress` if the panel height
+    /// plus `rows` does not fit the 320 rows.
+    pub fn set_row_offset(&mut self, rows: u16) -> Result<&mut Self, Error<PinError, SpiError>> {
+        if rows > scroll::GRAM_LINES - self.height {
+            return Err(Error::InvalidRowAddress);
+        }
+        self.row_offset = rows;
 
         Ok(self)
     }
 
+    fn memory_access_control_value(
+        &mut self,
+        value: u8,
+    ) -> Result<&mut Self, Error<PinError, SpiError>> {
+        self.madctl = value;
+        self.command(Command::MADCTL, Some(&[value]))
+    }
+
     /// Idle mode off.
     pub fn idle_off(&mut self) -> Result<&mut Self, Error<PinError, SpiError>> {
         self.command(Command::IDMOFF, None)?;
@@ -669,26 +1714,25 @@ impl<SPI, CS, DC, RST, PinError, SpiError> ST7789V<SPI, CS, DC, RST, PinError, S
     /// Sets the column address window.
     /// Each value represents one column line in the frame memory.
     ///
-    /// `xs` must always be equal or less than `xe`. When `xs` or `xe` are greater than
-    /// the maximum address, all data outside the range will be ignored.
+    /// `xe` is exclusive, the window covers columns `xs` to `xe - 1`, so `xe` must be
+    /// greater than zero. When `xs` or `xe` are greater than the maximum address, all data
+    /// outside the range will be ignored.
     pub fn column_address(
         &mut self,
         xs: u16,
         xe: u16,
     ) -> Result<&mut Self, Error<PinError, SpiError>> {
+        let end = xe.checked_sub(1).ok_or(Error::InvalidColumnAddress)?;
         self.command(
             Command::CASET,
             Some(&[
                 (xs >> 8) as u8,
                 (xs & 0xFF) as u8,
-                (xe.wrapping_sub(1) >> 8) as u8,
-                (xe.wrapping_sub(1) & 0xFF) as u8,
-                // (0x00) as u8,
-                // (xs & 0xFF) as u8,
-                // (((xe + 0x22) - 1) >> 8) as u8,
-                // (((xe + 0x22) - 1) & 0xFF) as u8,
+                (end >> 8) as u8,
+                (end & 0xFF) as u8,
             ]),
         )?;
+        self.columns = Some((xs, xe));
 
         Ok(self)
     }
@@ -696,31 +1740,32 @@ impl<SPI, CS, DC, RST, PinError, SpiError> ST7789V<SPI, CS, DC, RST, PinError, S
     /// Sets the row address window.
     /// Each value represents one page line in the frame memory.
     ///
-    /// `rs` must always be equal or less than `re`. Data outside the addressable
-    /// space will be ignored.
+    /// `re` is exclusive, the window covers rows `rs` to `re - 1`, so `re` must be greater
+    /// than zero. Data outside the addressable space will be ignored. Both are shifted by
+    /// the offset of `set_row_offset`.
     pub fn row_address(
         &mut self,
         rs: u16,
         re: u16,
     ) -> Result<&mut Self, Error<PinError, SpiError>> {
+        let end = re.checked_sub(1).ok_or(Error::InvalidRowAddress)?;
+        let offset = self.row_offset;
+        let (rs, re, end) = (rs.saturating_add(offset), re.saturating_add(offset), end.saturating_add(offset));
         self.command(
             Command::RASET,
             Some(&[
                 (rs >> 8) as u8,
                 (rs & 0xFF) as u8,
-                (re.wrapping_sub(1) >> 8) as u8,
-                (re.wrapping_sub(1) & 0xFF) as u8,
-                // (0x00) as u8,
-                // (rs & 0xFF) as u8,
-                // ((re - 1) >> 8) as u8,
-                // ((re - 1) & 0xFF) as u8,
+                (end >> 8) as u8,
+                (end & 0xFF) as u8,
             ]),
         )?;
+        self.rows = Some((rs, re));
 
         Ok(self)
     }
 
-    /// Sets the address window.
+    /// Sets the address window and starts a memory write (RAMWR), pixel data can follow.
     pub fn address_window(
         &mut self,
         xs: u16,
@@ -728,12 +1773,30 @@ impl<SPI, CS, DC, RST, PinError, SpiError> ST7789V<SPI, CS, DC, RST, PinError, S
         xe: u16,
         re: u16,
     ) -> Result<&mut Self, Error<PinError, SpiError>> {
-        if xs > xe || rs > re {
+        self.set_window(xs, rs, xe, re)?
+            .command(Command::RAMWR, None)?;
+        Ok(self)
+    }
+
+    /// Sets the address window (CASET + RASET) without starting a memory write, for
+    /// `read_pixels` or a following RAMWRC. Ends are exclusive like in `address_window`,
+    /// so a window with an end not past its start is empty and returns
+    /// `InvalidColumnAddress` or `InvalidRowAddress` instead of wrapping around.
+    pub fn set_window(
+        &mut self,
+        xs: u16,
+        rs: u16,
+        xe: u16,
+        re: u16,
+    ) -> Result<&mut Self, Error<PinError, SpiError>> {
+        if xs >= xe {
             return Err(Error::InvalidColumnAddress);
         }
+        if rs >= re {
+            return Err(Error::InvalidRowAddress);
+        }
         self.column_address(xs, xe)?
-            .row_address(rs, re)?
-        .command(Command::RAMWR, None)?;
+            .row_address(rs, re)?;
         Ok(self)
     }
 
@@ -745,6 +1808,7 @@ impl<SPI, CS, DC, RST, PinError, SpiError> ST7789V<SPI, CS, DC, RST, PinError, S
         where
             DELAY: DelayMs<u16>,
     {
+        self.batching = false;
         if let Some(cs) = self.cfg.cs.as_mut() {
             cs.set_high().map_err(Error::Pin)?;
         }
@@ -754,6 +1818,41 @@ impl<SPI, CS, DC, RST, PinError, SpiError> ST7789V<SPI, CS, DC, RST, PinError, S
         delay.delay_ms(1);
         self.cfg.rst.set_high().map_err(Error::Pin)?;
         delay.delay_ms(120);
+        self.mode = DisplayMode::Normal;
+        self.awake = false;
+        self.idle = false;
+        self.columns = None;
+        self.rows = None;
+
+        Ok(self)
+    }
+
+    /// Performs a hard reset timed with a microsecond delay.
+    ///
+    /// Same as `hard_reset` but pulses RST for the 10 µs the datasheet asks for instead
+    /// of whole milliseconds. The 120 ms before SLPOUT is still required, follow with
+    /// `init_no_reset` to initialize the display.
+    pub fn hard_reset_us<DELAY>(
+        &mut self,
+        delay: &mut DELAY,
+    ) -> Result<&mut Self, Error<PinError, SpiError>>
+        where
+            DELAY: DelayUs<u32>,
+    {
+        self.batching = false;
+        if let Some(cs) = self.cfg.cs.as_mut() {
+            cs.set_high().map_err(Error::Pin)?;
+        }
+
+        self.cfg.rst.set_low().map_err(Error::Pin)?;
+        delay.delay_us(10);
+        self.cfg.rst.set_high().map_err(Error::Pin)?;
+        delay.delay_us(120_000);
+        self.mode = DisplayMode::Normal;
+        self.awake = false;
+        self.idle = false;
+        self.columns = None;
+        self.rows = None;
 
         Ok(self)
     }
@@ -771,30 +1870,36 @@ impl<SPI, CS, DC, RST, PinError, SpiError> ST7789V<SPI, CS, DC, RST, PinError, S
     {
         self.command(Command::SWRESET, None)?;
         delay.delay_ms(150);
+        self.mode = DisplayMode::Normal;
+        self.awake = false;
+        self.idle = false;
+        self.columns = None;
+        self.rows = None;
 
         Ok(self)
     }
 
-    fn transfer_x_y(&self, x: u16, y: u16) -> (u16, u16) {
-        let mut start_x = x;
-        let mut start_y = y;
-        // change x, y
-        match self.rotate {
-            Rotate::Rotate90 => {
-                start_x = self.width.wrapping_sub(x); // to avoid negative
-                start_y = y
-            }
-            Rotate::Rotate180 => {
-                start_x = x;
-                start_y = self.height.wrapping_sub(y);  // to avoid negative
-            }
-            Rotate::Rotate270 => {
-                start_x = self.width.wrapping_sub(x);  // to avoid negative
-                start_y = self.height.wrapping_sub(y); // to avoid negative
-            }
-            _ => {}
+    /// Leave the panel in a known low power state and free the bus.
+    ///
+    /// Sends DISPOFF followed by SLPIN and raises the chip select pin. The driver does not
+    /// implement `Drop` because `release` hands the bus and pins back to the caller, so call
+    /// this (or `release`) before the driver goes out of scope. The display has to be
+    /// initialized again (or woken with `sleep_out` + `display_on`) afterwards.
+    pub fn shutdown<DELAY>(
+        &mut self,
+        delay: &mut DELAY,
+    ) -> Result<&mut Self, Error<PinError, SpiError>>
+        where
+            DELAY: DelayMs<u16>,
+    {
+        self.display_off()?.sleep_in(delay)?;
+
+        self.batching = false;
+        if let Some(cs) = self.cfg.cs.as_mut() {
+            cs.set_high().map_err(Error::Pin)?;
         }
-        (start_x, start_y)
+
+        Ok(self)
     }
 
     /// Transfer data from MCU to the frame memory.
@@ -804,100 +1909,1855 @@ impl<SPI, CS, DC, RST, PinError, SpiError> ST7789V<SPI, CS, DC, RST, PinError, S
         Ok(self)
     }
 
-    /// Sets a single pixel to the given color
-    pub fn pixel(
-        &mut self,
-        x: u16,
-        y: u16,
-        color: u16,
-    ) -> Result<&Self, Error<PinError, SpiError>> {
-        let (start_x, start_y) = self.transfer_x_y(x, y);
-        self.address_window(start_x, start_y, start_x, start_y,)?; // for save bandwidth
-        self.mem_write(&color.to_be_bytes())?;
+    /// Like `mem_write` for pixels held as `u16`, in the byte order of the MCU.
+    ///
+    /// The colors are encoded for the current color format (`PixelEncoding`)
+    /// through a small buffer on the way out, no copy of `data` is needed.
+    pub fn mem_write_u16(&mut self, data: &[u16]) -> Result<&mut Self, Error<PinError, SpiError>> {
+        self.command(Command::RAMWR, None)?;
+        self.write_colors(data.iter().copied())?;
 
         Ok(self)
     }
 
-    pub fn pixels<'a>(
-        &'a mut self,
-        xs: u16,
-        ys: u16,
-        xe: u16,
-        ye: u16,
-        colors: &mut dyn Iterator<Item=u16>,
-    ) -> Result<&'a mut Self, Error<PinError, SpiError>> {
-
-        let (start_x, start_y) = self.transfer_x_y(xs, ys);
-        let (end_x, end_y) = self.transfer_x_y(xe, ye);
-
-        let (min_x, max_x) = {
-            if start_x > end_x {
-                (end_x, start_x)
-            } else {
-                (start_x, end_x)
-            }
-        };
-
-        let (min_y, max_y) = {
-            if start_y > end_y {
-                (end_y, start_y)
-            } else {
-                (start_y, end_y)
-            }
-        };
-
-        self.address_window(min_x, min_y, max_x, max_y)?; // for save bandwidth
-        self.mem_write(&[])?;
-        if let Some(cs) = self.cfg.cs.as_mut() {
-            cs.set_low().map_err(Error::Pin)?;
-        }
-        self.cfg.dc.set_high().map_err(Error::Pin)?;
-
-        let colors_vec: Vec<u8> = colors.map(|x| x.to_be_bytes()).flatten().collect();
-
-        let pixel_slice = colors_vec.as_slice();
-        // fix this Cooperate with chatGPT
-        // TODO: this is inconsistent in embedded-graphics between Rectangle and Image
-        // See: https://github.com/jamwaffles/embedded-graphics/issues/182
-        let reversed_chunks: Vec<&[u8]> = pixel_slice.chunks((self.width * self.width) as usize).rev().collect();
-        let merged_data: &[u8] = &reversed_chunks.concat();
+    /// Writes a whole frame into the window set earlier, sending only RAMWR and `data`.
+    ///
+    /// The window from `set_window` or `address_window` stays in the panel until it is
+    /// changed or the panel is reset, and every RAMWR starts again at its top left
+    /// corner. Streaming video into a fixed window therefore needs the window once, not
+    /// once per frame.
+    pub fn refresh_frame(&mut self, data: &[u8]) -> Result<&mut Self, Error<PinError, SpiError>> {
+        self.mem_write(data)?;
 
-        for chunk in merged_data.chunks(4096) {
-            self.data(&chunk)?;
-        }
-        if let Some(cs) = self.cfg.cs.as_mut() {
-            cs.set_high().map_err(Error::Pin)?;
-        }
         Ok(self)
     }
 
-    fn command(
+    /// Draws a raw image of `w` x `h` pixels with its top left corner at `x`, `y`.
+    ///
+    /// `data` holds the pixels row by row in the current color format, for the default
+    /// 16 bit format that is two big endian bytes per pixel. Same as `write_raw_image_be`.
+    pub fn write_raw_image(
         &mut self,
-        cmd: Command,
-        params: Option<&[u8]>,
+        x: u16,
+        y: u16,
+        w: u16,
+        h: u16,
+        data: &[u8],
     ) -> Result<&mut Self, Error<PinError, SpiError>> {
-        if let Some(cs) = self.cfg.cs.as_mut() {
-            cs.set_low().map_err(Error::Pin)?;
+        self.write_raw_image_be(x, y, w, h, data)
+    }
+
+    /// Draws a little endian RGB565 image (`ImageRawLE` layout) of `w` x `h` pixels with
+    /// its top left corner at `x`, `y`.
+    ///
+    /// The bytes of every pixel are swapped on the way out, so `data` does not have to be
+    /// converted first. A trailing odd byte is ignored.
+    pub fn write_raw_image_le(
+        &mut self,
+        x: u16,
+        y: u16,
+        w: u16,
+        h: u16,
+        data: &[u8],
+    ) -> Result<&mut Self, Error<PinError, SpiError>> {
+        let (xe, ye) = Self::window_ends(x, y, w, h)?;
+        self.address_window(x, y, xe, ye)?;
+        let colors = data.chunks_exact(2).map(|c| u16::from_le_bytes([c[0], c[1]]));
+        self.write_colors(colors)?;
+
+        Ok(self)
+    }
+
+    /// Draws a big endian raw image (`ImageRawBE` layout) of `w` x `h` pixels with its top
+    /// left corner at `x`, `y`. The bytes are sent as they are.
+    pub fn write_raw_image_be(
+        &mut self,
+        x: u16,
+        y: u16,
+        w: u16,
+        h: u16,
+        data: &[u8],
+    ) -> Result<&mut Self, Error<PinError, SpiError>> {
+        let (xe, ye) = Self::window_ends(x, y, w, h)?;
+        self.address_window(x, y, xe, ye)?;
+        self.write_chunks(data, &mut |_, _| {})?;
+
+        Ok(self)
+    }
+
+    /// Copies a framebuffer of `fb_w` x `fb_h` pixels, row by row, to the top left corner
+    /// of the screen.
+    ///
+    /// The whole blit is one window and one memory write, rotation is up to MADCTL as
+    /// everywhere else. Same result as `pixels` over `fb`. Returns `InvalidBufferSize` if
+    /// `fb` is shorter than `fb_w * fb_h`.
+    pub fn blit_logical(
+        &mut self,
+        fb: &[u16],
+        fb_w: u16,
+        fb_h: u16,
+    ) -> Result<&mut Self, Error<PinError, SpiError>> {
+        let (w, h) = (fb_w as usize, fb_h as usize);
+        if fb.len() < w * h {
+            return Err(Error::InvalidBufferSize);
+        }
+        if fb_w > self.width {
+            return Err(Error::InvalidColumnAddress);
+        }
+        if fb_h > self.height {
+            return Err(Error::InvalidRowAddress);
+        }
+        self.address_window(0, 0, fb_w, fb_h)?;
+        self.write_colors(fb[..w * h].iter().copied())?;
+
+        Ok(self)
+    }
+
+    /// Draws a big endian raw image of `w` x `h` pixels rotated clockwise by `rotation`,
+    /// with its top left corner (after rotating) at `x`, `y`.
+    ///
+    /// The pixels are picked out of `data` in rotated order on the way out, the driver's
+    /// own rotation (`set_rotate`) is not touched, so assets of different orientations can
+    /// share a screen. For 90 and 270 the image covers `h` x `w` pixels on screen. Returns
+    /// `InvalidBufferSize` if `data` is shorter than `w * h * 2` bytes.
+    pub fn draw_raw_image_rotated(
+        &mut self,
+        x: u16,
+        y: u16,
+        w: u16,
+        h: u16,
+        data: &[u8],
+        rotation: Rotate,
+    ) -> Result<&mut Self, Error<PinError, SpiError>> {
+        let (w, h) = (w as usize, h as usize);
+        if data.len() < w * h * 2 {
+            return Err(Error::InvalidBufferSize);
+        }
+        let (out_w, out_h) = match rotation {
+            Rotate::Rotate90 | Rotate::Rotate270 => (h, w),
+            Rotate::Rotate0 | Rotate::Rotate180 => (w, h),
+        };
+
+        let (xe, ye) = Self::window_ends(x, y, out_w as u16, out_h as u16)?;
+        self.address_window(x, y, xe, ye)?;
+        let colors = (0..out_h).flat_map(|oy| (0..out_w).map(move |ox| (ox, oy))).map(|(ox, oy)| {
+            let (sx, sy) = match rotation {
+                Rotate::Rotate0 => (ox, oy),
+                Rotate::Rotate90 => (oy, h - 1 - ox),
+                Rotate::Rotate180 => (w - 1 - ox, h - 1 - oy),
+                Rotate::Rotate270 => (w - 1 - oy, ox),
+            };
+            let i = (sy * w + sx) * 2;
+            u16::from_be_bytes([data[i], data[i + 1]])
+        });
+        self.write_colors(colors)?;
+
+        Ok(self)
+    }
+
+    /// Exclusive ends of a `w` x `h` window at `x`, `y`, `InvalidColumnAddress` or
+    /// `InvalidRowAddress` when they do not fit in a `u16`.
+    fn window_ends(x: u16, y: u16, w: u16, h: u16) -> Result<(u16, u16), Error<PinError, SpiError>> {
+        let xe = x.checked_add(w).ok_or(Error::InvalidColumnAddress)?;
+        let ye = y.checked_add(h).ok_or(Error::InvalidRowAddress)?;
+        Ok((xe, ye))
+    }
+
+    /// Sends `data` into the current write window in 4096 byte chunks, calling
+    /// `progress(bytes_written, total_bytes)` after each one.
+    fn write_chunks(
+        &mut self,
+        data: &[u8],
+        progress: &mut dyn FnMut(usize, usize),
+    ) -> Result<(), Error<PinError, SpiError>> {
+        self.select()?;
+        self.cfg.dc.set_high().map_err(Error::Pin)?;
+
+        let mut written = 0;
+        for chunk in data.chunks(4096) {
+            self.data(chunk)?;
+            written += chunk.len();
+            progress(written, data.len());
+        }
+        self.deselect()?;
+        Ok(())
+    }
+
+    /// Draws a 1 bit per pixel bitmap of `w` x `h` pixels with its top left corner at `x`, `y`.
+    ///
+    /// Set bits are drawn in `fg`, cleared bits in `bg`. Bits are read MSB first and every
+    /// row starts on a new byte, so a row takes `(w + 7) / 8` bytes. Returns
+    /// `InvalidBufferSize` without drawing if `bits` holds fewer than `h` such rows.
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_mono_bitmap(
+        &mut self,
+        x: u16,
+        y: u16,
+        w: u16,
+        h: u16,
+        bits: &[u8],
+        fg: u16,
+        bg: u16,
+    ) -> Result<&mut Self, Error<PinError, SpiError>> {
+        let stride = (w as usize).div_ceil(8);
+        if bits.len() < stride * h as usize {
+            return Err(Error::InvalidBufferSize);
+        }
+        let (xe, ye) = Self::window_ends(x, y, w, h)?;
+
+        self.address_window(x, y, xe, ye)?;
+        let colors = (0..h as usize).flat_map(|row| {
+            (0..w as usize).map(move |col| {
+                let byte = bits[row * stride + col / 8];
+                if byte & (0x80 >> (col % 8)) != 0 {
+                    fg
+                } else {
+                    bg
+                }
+            })
+        });
+        self.write_colors(colors)?;
+
+        Ok(self)
+    }
+
+    /// Writes rows of `width` pixels starting at `xs`, `ys`, one row after the other.
+    ///
+    /// The window reaches from `ys` to the bottom of the panel and as many rows are written
+    /// as `rows` yields, so the same row can be repeated (`iter::repeat(row).take(n)`) for
+    /// patterns, or every row computed on the fly for gradients. Rows shorter than `width`
+    /// are padded with black, longer rows are cut. Rows past the bottom of the panel are
+    /// dropped rather than wrapped to the top of the window. Returns
+    /// `InvalidColumnAddress` if `xs + width` is past the width and `InvalidRowAddress` if
+    /// `ys` is not above the bottom, before anything is sent.
+    pub fn write_rows<'r, I>(
+        &mut self,
+        xs: u16,
+        ys: u16,
+        width: u16,
+        rows: I,
+    ) -> Result<&mut Self, Error<PinError, SpiError>>
+        where
+            I: Iterator<Item=&'r [u16]>,
+    {
+        let xe = xs.checked_add(width).ok_or(Error::InvalidColumnAddress)?;
+        if xe > self.width {
+            return Err(Error::InvalidColumnAddress);
+        }
+        if ys >= self.height {
+            return Err(Error::InvalidRowAddress);
+        }
+        self.address_window(xs, ys, xe, self.height)?;
+        let colors = rows.take(self.height.saturating_sub(ys) as usize).flat_map(|row| {
+            row.iter()
+                .copied()
+                .chain(core::iter::repeat(0))
+                .take(width as usize)
+        });
+        self.write_colors(colors)?;
+
+        Ok(self)
+    }
+
+    /// Size in bytes of a full frame in the current color format (`color_mode`), what
+    /// `write_framebuffer` expects. 12 bit pixels pack two into three bytes.
+    pub fn frame_bytes(&self) -> usize {
+        self.pixel_data_len(self.width as usize * self.height as usize)
+    }
+
+    /// Writes a whole frame, row by row in the current color format.
+    ///
+    /// Returns `InvalidBufferSize` without touching the panel if `data` is not exactly
+    /// `frame_bytes` long.
+    pub fn write_framebuffer(
+        &mut self,
+        data: &[u8],
+    ) -> Result<&mut Self, Error<PinError, SpiError>> {
+        self.write_framebuffer_with_progress(data, |_, _| {})
+    }
+
+    /// Like `write_framebuffer`, calling `kick` between chunks so a watchdog can be fed
+    /// during a long transfer.
+    ///
+    /// Chunks are 4096 bytes, about 33 ms at 1 MHz and 3.3 ms at 10 MHz SPI clock. If
+    /// that is too close to the watchdog period, use `pixels_with_kick`, whose 512 byte
+    /// chunks take an eighth of that.
+    pub fn write_framebuffer_with_kick(
+        &mut self,
+        data: &[u8],
+        kick: &mut dyn FnMut(),
+    ) -> Result<&mut Self, Error<PinError, SpiError>> {
+        self.write_framebuffer_with_progress(data, |_, _| kick())
+    }
+
+    /// Like `write_framebuffer`, calling `progress(bytes_written, total_bytes)` after every
+    /// 4096 byte chunk, e.g. for a progress bar or to kick a watchdog.
+    pub fn write_framebuffer_with_progress<F>(
+        &mut self,
+        data: &[u8],
+        mut progress: F,
+    ) -> Result<&mut Self, Error<PinError, SpiError>>
+        where
+            F: FnMut(usize, usize),
+    {
+        if data.len() != self.frame_bytes() {
+            return Err(Error::InvalidBufferSize);
+        }
+        let (width, height) = (self.width, self.height);
+        self.address_window(0, 0, width, height)?;
+        self.write_chunks(data, &mut progress)?;
+
+        Ok(self)
+    }
+
+    /// Fills the whole panel with a single color.
+    ///
+    /// Writes the whole frame memory in scan order, one prepared buffer sent over and
+    /// over. `DrawTarget::clear` uses it too.
+    pub fn fill_screen(&mut self, color: u16) -> Result<&mut Self, Error<PinError, SpiError>> {
+        let count = self.width as usize * self.height as usize;
+        self.address_window(0, 0, self.width, self.height)?;
+        self.write_solid(color, count)?;
+
+        Ok(self)
+    }
+
+    /// Erases the window `xs`..`xe`, `ys`..`ye` (ends exclusive) to black, e.g. the old
+    /// position of a sprite.
+    ///
+    /// Goes out as one window and one write of a repeated buffer. Same bounds checks as
+    /// `pixels`.
+    pub fn erase_rect(
+        &mut self,
+        xs: u16,
+        ys: u16,
+        xe: u16,
+        ye: u16,
+    ) -> Result<&mut Self, Error<PinError, SpiError>> {
+        if xs.max(xe) > self.width {
+            return Err(Error::InvalidColumnAddress);
+        }
+        if ys.max(ye) > self.height {
+            return Err(Error::InvalidRowAddress);
+        }
+        self.address_window(xs, ys, xe, ye)?;
+        self.write_solid(0, xe.saturating_sub(xs) as usize * ye.saturating_sub(ys) as usize)?;
+
+        Ok(self)
+    }
+
+    /// Fills the window `xs`..`xe`, `ys`..`ye` (ends exclusive) with a vertical gradient
+    /// from `top` on the first row to `bottom` on the last.
+    ///
+    /// Red, green and blue are interpolated separately in their 5/6/5 bits, so no channel
+    /// carries into the next. Rows are streamed as they are computed, no frame buffer.
+    pub fn fill_gradient(
+        &mut self,
+        xs: u16,
+        ys: u16,
+        xe: u16,
+        ye: u16,
+        top: u16,
+        bottom: u16,
+    ) -> Result<&mut Self, Error<PinError, SpiError>> {
+        let width = xe.saturating_sub(xs) as usize;
+        let last = ye.saturating_sub(ys).saturating_sub(1) as u32;
+        let mut colors = (0..=last).flat_map(|row| {
+            core::iter::repeat_n(lerp_rgb565(top, bottom, row, last), width)
+        });
+        self.pixels(xs, ys, xe, ye, &mut colors)
+    }
+
+    /// Fills a circle of `radius` around `cx`, `cy` with `color`.
+    ///
+    /// The midpoint circle algorithm gives the horizontal span of every row, and each span
+    /// goes out as one window and one memory write. Parts outside the panel are clipped,
+    /// so the center may sit on or past an edge.
+    pub fn fill_circle(
+        &mut self,
+        cx: i32,
+        cy: i32,
+        radius: u16,
+        color: u16,
+    ) -> Result<&mut Self, Error<PinError, SpiError>> {
+        let (mut x, mut y) = (radius as i32, 0);
+        let mut err = 1 - x;
+        while x >= y {
+            self.fill_span(cx - x, cx + x, cy + y, color)?;
+            if y != 0 {
+                self.fill_span(cx - x, cx + x, cy - y, color)?;
+            }
+            // rows at +-x take their widest span, the one before x steps in
+            if err >= 0 && x != y {
+                self.fill_span(cx - y, cx + y, cy + x, color)?;
+                self.fill_span(cx - y, cx + y, cy - x, color)?;
+            }
+            y += 1;
+            if err < 0 {
+                err += 2 * y + 1;
+            } else {
+                x -= 1;
+                err += 2 * (y - x) + 1;
+            }
+        }
+
+        Ok(self)
+    }
+
+    /// Fills row `y` from `x0` to `x1` (both inclusive), clipped to the panel.
+    fn fill_span(&mut self, x0: i32, x1: i32, y: i32, color: u16) -> Result<(), Error<PinError, SpiError>> {
+        if y < 0 || y >= self.height as i32 {
+            return Ok(());
+        }
+        let (x0, x1) = (x0.max(0), x1.min(self.width as i32 - 1));
+        if x0 > x1 {
+            return Ok(());
+        }
+        let (xs, xe, y) = (x0 as u16, x1 as u16 + 1, y as u16);
+        self.pixels(xs, y, xe, y + 1, &mut core::iter::repeat_n(color, (xe - xs) as usize))?;
+        Ok(())
+    }
+
+    /// Fills the panel with vertical color bars: red, green, blue, white and black from
+    /// left to right, for checking color order, rotation and that the whole panel is driven.
+    pub fn draw_test_pattern(&mut self) -> Result<&mut Self, Error<PinError, SpiError>> {
+        const BARS: [u16; 5] = [0xF800, 0x07E0, 0x001F, 0xFFFF, 0x0000];
+
+        let (width, height) = (self.width, self.height);
+        let bar = width / BARS.len() as u16;
+        for (i, &color) in BARS.iter().enumerate() {
+            let xs = bar * i as u16;
+            // the last bar takes whatever the division left over
+            let xe = if i == BARS.len() - 1 { width } else { xs + bar };
+            let count = (xe - xs) as usize * height as usize;
+            self.pixels(xs, 0, xe, height, &mut core::iter::repeat_n(color, count))?;
+        }
+
+        Ok(self)
+    }
+
+    /// Sets a single pixel to the given color
+    ///
+    /// Returns `InvalidColumnAddress` if `x` is not below the width and `InvalidRowAddress`
+    /// if `y` is not below the height, instead of drawing the pixel somewhere else.
+    pub fn pixel(
+        &mut self,
+        x: u16,
+        y: u16,
+        color: u16,
+    ) -> Result<&Self, Error<PinError, SpiError>> {
+        if x >= self.width {
+            return Err(Error::InvalidColumnAddress);
+        }
+        if y >= self.height {
+            return Err(Error::InvalidRowAddress);
+        }
+        self.pixel_unchecked(x, y, color)
+    }
+
+    /// Like `pixel` without the bounds check, for loops that already stay on screen.
+    /// Coordinates off screen end up wherever the address window wraps to.
+    pub fn pixel_unchecked(
+        &mut self,
+        x: u16,
+        y: u16,
+        color: u16,
+    ) -> Result<&Self, Error<PinError, SpiError>> {
+        let end_x = x.checked_add(1).ok_or(Error::InvalidColumnAddress)?;
+        let end_y = y.checked_add(1).ok_or(Error::InvalidRowAddress)?;
+        self.address_window(x, y, end_x, end_y)?; // for save bandwidth
+        self.write_colors(core::iter::once(color))?;
+
+        Ok(self)
+    }
+
+    /// Fills the window `xs`..`xe`, `ys`..`ye` (ends exclusive) with `colors`, row by row.
+    ///
+    /// Returns `InvalidColumnAddress` if `xe` is past the width and `InvalidRowAddress`
+    /// if `ye` is past the height, before anything is sent, rather than letting the
+    /// window wrap around frame memory.
+    pub fn pixels<'a>(
+        &'a mut self,
+        xs: u16,
+        ys: u16,
+        xe: u16,
+        ye: u16,
+        colors: &mut dyn Iterator<Item=u16>,
+    ) -> Result<&'a mut Self, Error<PinError, SpiError>> {
+        self.pixels_with_progress(xs, ys, xe, ye, colors, |_, _| {})
+    }
+
+    /// Like `pixels`, calling `kick` between chunks so a watchdog can be fed during a long
+    /// transfer. Chunks are at most 512 bytes, about 4 ms at 1 MHz SPI clock.
+    pub fn pixels_with_kick<'a>(
+        &'a mut self,
+        xs: u16,
+        ys: u16,
+        xe: u16,
+        ye: u16,
+        colors: &mut dyn Iterator<Item=u16>,
+        kick: &mut dyn FnMut(),
+    ) -> Result<&'a mut Self, Error<PinError, SpiError>> {
+        self.pixels_with_progress(xs, ys, xe, ye, colors, |_, _| kick())
+    }
+
+    /// Like `pixels`, calling `progress(bytes_written, total_bytes)` after every bus
+    /// chunk. `total_bytes` is the size of the window, two bytes per pixel.
+    pub fn pixels_with_progress<'a, F>(
+        &'a mut self,
+        xs: u16,
+        ys: u16,
+        xe: u16,
+        ye: u16,
+        colors: &mut dyn Iterator<Item=u16>,
+        mut progress: F,
+    ) -> Result<&'a mut Self, Error<PinError, SpiError>>
+        where
+            F: FnMut(usize, usize),
+    {
+        if xs.max(xe) > self.width {
+            return Err(Error::InvalidColumnAddress);
+        }
+        if ys.max(ye) > self.height {
+            return Err(Error::InvalidRowAddress);
+        }
+        let total = self.pixel_data_len(xe.saturating_sub(xs) as usize * ye.saturating_sub(ys) as usize);
+        self.address_window(xs, ys, xe, ye)?; // for save bandwidth
+        let bytes = self.pixel_bytes(colors);
+        self.write_bytes_progress(bytes, total, &mut progress)?;
+
+        Ok(self)
+    }
+
+    /// Streams colors into the current write window, encoded for the current color format.
+    pub(crate) fn write_colors<I>(&mut self, colors: I) -> Result<(), Error<PinError, SpiError>>
+        where
+            I: Iterator<Item=u16>,
+    {
+        let bytes = self.pixel_bytes(colors);
+        self.write_bytes(bytes)
+    }
+
+    /// Whether colors go out as plain big endian RGB565, no packing and no correction
+    #[cfg(feature = "graphics")]
+    pub(crate) fn plain_colors(&self) -> bool {
+        self.rgb565_on_bus() && self.color_correction.is_none()
+    }
+
+    /// `color` after the color correction, if any
+    pub(crate) fn corrected(&self, color: u16) -> u16 {
+        match self.color_correction {
+            Some(gains) => correct_color(color, gains),
+            None => color,
+        }
+    }
+
+    /// The `PixelEncoding` COLMOD selects for the control interface
+    fn encoder(&self) -> Encoder {
+        match self.colmod & 0b111 {
+            0b011 => Encoder::of::<Bits12>(),
+            0b110 | 0b111 => Encoder::of::<Bits18>(),
+            _ => Encoder::of::<Bits16>(),
+        }
+    }
+
+    /// Whether pixels go out as two RGB565 bytes each, so solid colors can be repeated
+    /// byte pairs or 16 bit words
+    pub(crate) fn rgb565_on_bus(&self) -> bool {
+        self.encoder().pixels == 1 && self.pixel_data_len(1) == 2
+    }
+
+    /// `colors` encoded for the current color format
+    fn pixel_bytes<I>(&self, colors: I) -> PixelBytes<I> {
+        PixelBytes {
+            colors,
+            encoder: self.encoder(),
+            correction: self.color_correction,
+            out: [0; 3],
+            len: 0,
+            pos: 0,
+        }
+    }
+
+    /// Bytes `pixels` pixels take on the bus in the current color format
+    fn pixel_data_len(&self, pixels: usize) -> usize {
+        (self.encoder().data_len)(pixels)
+    }
+
+    /// Writes `count` pixels of `color` into the current write window.
+    fn write_solid(&mut self, color: u16, count: usize) -> Result<(), Error<PinError, SpiError>> {
+        if !self.rgb565_on_bus() {
+            return self.write_colors(core::iter::repeat_n(color, count));
         }
+        let color = self.corrected(color);
+        let mut buf = [0u8; 512];
+        for pixel in buf.chunks_exact_mut(2) {
+            pixel.copy_from_slice(&color.to_be_bytes());
+        }
+
+        self.select()?;
+        self.cfg.dc.set_high().map_err(Error::Pin)?;
+        let mut left = count * 2;
+        while left > 0 {
+            let n = left.min(buf.len());
+            self.data(&buf[..n])?;
+            left -= n;
+        }
+        self.deselect()?;
+        Ok(())
+    }
+
+    /// Streams pixel data into the current write window, buffered into bus sized chunks.
+    pub(crate) fn write_bytes<I>(&mut self, bytes: I) -> Result<(), Error<PinError, SpiError>>
+        where
+            I: Iterator<Item=u8>,
+    {
+        self.write_bytes_progress(bytes, 0, &mut |_, _| {})
+    }
+
+    /// `write_bytes`, calling `progress(bytes_written, total)` after every chunk.
+    fn write_bytes_progress<I>(
+        &mut self,
+        bytes: I,
+        total: usize,
+        progress: &mut dyn FnMut(usize, usize),
+    ) -> Result<(), Error<PinError, SpiError>>
+        where
+            I: Iterator<Item=u8>,
+    {
+        self.select()?;
+        self.cfg.dc.set_high().map_err(Error::Pin)?;
+
+        let mut buf = [0u8; 512];
+        let mut len = 0;
+        let mut written = 0;
+        for byte in bytes {
+            buf[len] = byte;
+            len += 1;
+            if len == buf.len() {
+                self.data(&buf)?;
+                written += len;
+                progress(written, total);
+                len = 0;
+            }
+        }
+        if len > 0 {
+            self.data(&buf[..len])?;
+            progress(written + len, total);
+        }
+
+        self.deselect()?;
+        Ok(())
+    }
+
+    fn command(
+        &mut self,
+        cmd: Command,
+        params: Option<&[u8]>,
+    ) -> Result<&mut Self, Error<PinError, SpiError>> {
+        #[cfg(feature = "log")]
+        {
+            // pixel data can come in as parameters (mem_write), only show the start of it
+            let params = params.unwrap_or(&[]);
+            let head = &params[..params.len().min(16)];
+            log::trace!("st7789v: {:?} {:02X?} ({} bytes)", cmd, head, params.len());
+        }
+
+        self.select()?;
         self.cfg.dc.set_low().map_err(Error::Pin)?;
-        self.spi.write(&[cmd.value()]).map_err(Error::Spi)?;
+        self.spi_write(&[cmd.value()])?;
+        // tracked here so init and run_sequence are seen too
+        match cmd {
+            Command::SLPOUT => self.awake = true,
+            Command::SLPIN => self.awake = false,
+            Command::IDMON => self.idle = true,
+            Command::IDMOFF => self.idle = false,
+            Command::RAMWR | Command::RAMWRC => {
+                if let Some((delay_us, us)) = self.ramwr_settle {
+                    delay_us(us);
+                }
+            }
+            _ => {}
+        }
 
         if let Some(params) = params {
-            if let Some(cs) = self.cfg.cs.as_mut() {
-                cs.set_low().map_err(Error::Pin)?;
-            }
             self.cfg.dc.set_high().map_err(Error::Pin)?;
             self.data(params)?;
-            if let Some(cs) = self.cfg.cs.as_mut() {
-                cs.set_high().map_err(Error::Pin)?;
-            }
         }
+        // release the bus after every command so several displays can share it, the
+        // panel carries on with RAMWR data after a CS pause
+        self.deselect()?;
 
         Ok(self)
     }
 
+    /// Assert CS (if there is one) and wait the CS setup delay. Nothing to do in a batch,
+    /// CS is low already.
+    fn select(&mut self) -> Result<(), Error<PinError, SpiError>> {
+        if self.batching {
+            return Ok(());
+        }
+        if let Some(cs) = self.cfg.cs.as_mut() {
+            cs.set_low().map_err(Error::Pin)?;
+            if let Some((delay_us, us)) = self.cs_setup {
+                delay_us(us);
+            }
+        }
+        Ok(())
+    }
+
+    /// Raise CS (if there is one), unless a batch keeps it low.
+    fn deselect(&mut self) -> Result<(), Error<PinError, SpiError>> {
+        if self.batching {
+            return Ok(());
+        }
+        if let Some(cs) = self.cfg.cs.as_mut() {
+            cs.set_high().map_err(Error::Pin)?;
+        }
+        Ok(())
+    }
+
     fn data(&mut self, data: &[u8]) -> Result<&mut Self, Error<PinError, SpiError>> {
-        self.spi.write(data).map_err(Error::Spi)?;
+        #[cfg(feature = "log")]
+        log::trace!("st7789v: data, {} bytes", data.len());
+
+        self.spi_write(data)?;
         Ok(self)
     }
+
+    /// Write to the bus, retrying up to `write_retries` times before giving up with the
+    /// last error.
+    fn spi_write(&mut self, bytes: &[u8]) -> Result<(), Error<PinError, SpiError>> {
+        let mut retries = self.write_retries;
+        loop {
+            match self.spi.write(bytes) {
+                Ok(()) => return Ok(()),
+                Err(_) if retries > 0 => retries -= 1,
+                Err(e) => return Err(Error::Spi(e)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use crate::command::Command;
+    use crate::mock::{display, Event, FlakySpi, Log, MockDisplay, MockPin, MockSpi, PinKind};
+    use crate::{CabcMode, ColorFormat, ColorOrder, DisplayMode, Error, Flip, MemAccCtrlConfig, Rotate, ST7789V, DEFAULT_INIT};
+
+    #[test]
+    fn init_command_stream() {
+        let (mut display, mut delay, log) = display(240, 320);
+        display.init(&mut delay).unwrap();
+
+        let events = log.events();
+        let rst: Vec<&Event> = events.iter().filter(|e| matches!(e, Event::Rst(_))).collect();
+        assert_eq!(rst, [&Event::Rst(false), &Event::Rst(true)]);
+        // reset pulse and wait, then the settle time before DISPON
+        assert_eq!(log.delay_ms(), 122 + 120);
+        assert_eq!(log.delay_ms(), crate::INIT_DELAY_MS);
+        let dispon = events.iter().position(|e| *e == Event::Write(vec![Command::DISPON.value()]));
+        let before = events[..dispon.unwrap()].iter().rev();
+        let last = before.take_while(|e| !matches!(e, Event::Write(_))).find(|e| matches!(e, Event::Delay(_)));
+        assert_eq!(last, Some(&Event::Delay(120)));
+
+        #[rustfmt::skip]
+        let expected: &[u8] = &[
+            0x36, 0x00,
+            0x3A, 0x05,
+            0x21,
+            0x2A, 0x00, 0x00, 0x01, 0x3F,
+            0x2B, 0x00, 0x00, 0x00, 0xEF,
+            0xB2, 0x0C, 0x0C, 0x00, 0x33, 0x33,
+            0xB7, 0x35,
+            0xBB, 0x1F,
+            0xC0, 0x2C,
+            0xC2, 0x01,
+            0xC3, 0x12,
+            0xC4, 0x20,
+            0xC6, 0x0F,
+            0xD0, 0xA4, 0xA1,
+            0xE0, 0xD0, 0x08, 0x11, 0x08, 0x0C, 0x15, 0x39, 0x33, 0x50, 0x36, 0x13, 0x14, 0x29, 0x2D,
+            0xE1, 0xD0, 0x08, 0x10, 0x08, 0x06, 0x06, 0x39, 0x44, 0x51, 0x0B, 0x16, 0x14, 0x2F, 0x31,
+            0x21,
+            0x11,
+            0x29,
+        ];
+        assert_eq!(log.bytes(), expected);
+        assert_eq!(log.commands().len(), DEFAULT_INIT.len());
+    }
+
+    #[test]
+    fn size_must_fit_gram() {
+        let log = Log::default();
+        let display = ST7789V::with_cs(
+            MockSpi(log.clone()),
+            MockPin(log.clone(), PinKind::Cs),
+            MockPin(log.clone(), PinKind::Dc),
+            MockPin(log.clone(), PinKind::Rst),
+            320,
+            240,
+        );
+
+        assert!(matches!(display, Err(Error::InvalidSize)));
+        assert!(log.events().is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "does not fit")]
+    fn new_panics_on_oversized() {
+        let log = Log::default();
+        let _ = MockDisplay::new(
+            MockSpi(log.clone()),
+            MockPin(log.clone(), PinKind::Dc),
+            MockPin(log.clone(), PinKind::Rst),
+            360,
+            320,
+        );
+    }
+
+    #[test]
+    fn reinit_after_soft_reset() {
+        let (mut display, mut delay, log) = display(240, 320);
+        display.memory_access_control(MemAccCtrlConfig::rotate_90()).unwrap();
+        display.set_porch(0x08, 0x08, false, 0x22, 0x22).unwrap();
+        display.soft_reset(&mut delay).unwrap();
+
+        log.clear();
+        display.reinit_registers(&mut delay).unwrap();
+        let commands = log.commands();
+        assert_eq!(commands.len(), DEFAULT_INIT.len() - 2);
+        assert_eq!(commands[0], (Command::MADCTL.value(), vec![MemAccCtrlConfig::rotate_90().value()]));
+        assert!(commands.contains(&(Command::PORCTRL.value(), vec![0x08, 0x08, 0x00, 0x22, 0x22])));
+        assert!(!commands.iter().any(|(cmd, _)| *cmd == Command::SLPOUT.value()));
+    }
+
+    #[test]
+    fn command_2_unlock() {
+        let (mut display, _, log) = display(240, 320);
+        display.enable_command_2().unwrap().disable_command_2().unwrap();
+
+        assert_eq!(
+            log.commands(),
+            [
+                (Command::CMD2EN.value(), vec![0x5A, 0x69, 0x02, 0x01]),
+                (0xFE, vec![]),
+                (0xEF, vec![]),
+                (Command::CMD2EN.value(), vec![0x5A, 0x69, 0x02, 0x00]),
+            ]
+        );
+    }
+
+    #[test]
+    fn init_without_inversion() {
+        let (mut display, mut delay, log) = display(240, 320);
+        display.invert_on_init(false).init_no_reset(&mut delay).unwrap();
+
+        let commands = log.commands();
+        assert!(!commands.iter().any(|(cmd, _)| *cmd == Command::INVON.value()));
+        assert_eq!(commands.iter().filter(|(cmd, _)| *cmd == Command::INVOFF.value()).count(), 2);
+    }
+
+    #[test]
+    fn flip_sets_mx_my() {
+        let (mut display, mut delay, log) = display(240, 320);
+        let config = || {
+            let mut config = MemAccCtrlConfig::default();
+            config.color_order(ColorOrder::Bgr);
+            config
+        };
+        let base = config().value() & 0x3F;
+        display.memory_access_control(config()).unwrap();
+        display.set_flip(Flip::Horizontal).unwrap().set_flip(Flip::Both).unwrap();
+        display.set_rotate(Rotate::Rotate0).unwrap();
+
+        assert_eq!(
+            log.commands()[1..],
+            [
+                (Command::MADCTL.value(), vec![base | 0x40]),
+                (Command::MADCTL.value(), vec![base | 0xC0]),
+                (Command::MADCTL.value(), vec![base | 0xC0]),
+            ]
+        );
+        log.clear();
+        display.init_no_reset(&mut delay).unwrap();
+        assert_eq!(log.commands()[0], (Command::MADCTL.value(), vec![base | 0xC0]));
+    }
+
+    #[test]
+    fn native_size_ignores_rotation() {
+        let (mut display, _, _) = display(240, 280);
+        display.set_rotate(Rotate::Rotate90).unwrap();
+
+        assert_eq!(display.native_size(), (240, 280));
+    }
+
+    #[test]
+    fn mode_tracking() {
+        let (mut display, mut delay, _) = display(240, 320);
+        assert_eq!(display.current_mode(), DisplayMode::Normal);
+
+        display.partial_display_mode().unwrap();
+        assert_eq!(display.current_mode(), DisplayMode::Partial);
+        display.soft_reset(&mut delay).unwrap();
+        assert_eq!(display.current_mode(), DisplayMode::Normal);
+    }
+
+    #[test]
+    fn all_pixels_modes() {
+        let (mut display, _, log) = display(240, 320);
+        display.all_pixels_on().unwrap().all_pixels_off().unwrap().normal_display().unwrap();
+
+        assert_eq!(log.bytes(), [0x23, 0x22, 0x13]);
+    }
+
+    #[test]
+    fn write_retries() {
+        let log = Log::default();
+        let failures = Rc::new(Cell::new(2));
+        let mut display: ST7789V<_, MockPin, _, _, _, _> = ST7789V::new(
+            FlakySpi(log.clone(), failures.clone()),
+            MockPin(log.clone(), PinKind::Dc),
+            MockPin(log.clone(), PinKind::Rst),
+            240,
+            320,
+        );
+
+        assert!(matches!(display.display_on(), Err(Error::Spi(()))));
+        failures.set(2);
+        display.set_write_retries(2);
+        display.display_on().unwrap();
+        assert_eq!(log.bytes(), [Command::DISPON.value()]);
+    }
+
+    #[test]
+    fn progress_per_chunk() {
+        let (mut display, _, _) = display(240, 320);
+        let frame = vec![0u8; display.frame_bytes()];
+        let mut calls = Vec::new();
+        display.write_framebuffer_with_progress(&frame, |done, total| calls.push((done, total))).unwrap();
+
+        assert_eq!(calls.len(), frame.len().div_ceil(4096));
+        assert_eq!(calls[0], (4096, frame.len()));
+        assert_eq!(calls.last(), Some(&(frame.len(), frame.len())));
+
+        let mut calls = Vec::new();
+        display.pixels_with_progress(0, 0, 20, 20, &mut core::iter::repeat_n(0, 400), |done, total| {
+            calls.push((done, total))
+        }).unwrap();
+        assert_eq!(calls, [(512, 800), (800, 800)]);
+    }
+
+    #[test]
+    fn nvm_write_sequence() {
+        let (mut display, mut delay, log) = display(240, 320);
+        display.nvm_write(0x12, 0x85, &mut delay).unwrap();
+
+        assert_eq!(
+            log.commands(),
+            [(0xFC, vec![0x12, 0x85]), (0xFE, vec![0x29, 0xA5])]
+        );
+        assert_eq!(log.events().last(), Some(&Event::Delay(100)));
+    }
+
+    #[test]
+    fn gradient_per_channel() {
+        assert_eq!(crate::lerp_rgb565(0x0000, 0xFFFF, 1, 2), 0x7BEF);
+        // packed u16 interpolation would carry blue into green here
+        assert_eq!(crate::lerp_rgb565(0x001F, 0x0000, 1, 2), 0x000F);
+
+        let (mut display, _, log) = display(240, 320);
+        display.fill_gradient(0, 0, 2, 3, 0xF800, 0x001F).unwrap();
+        let pixels: Vec<u8> = log.commands().last().unwrap().1.clone();
+        assert_eq!(
+            pixels,
+            [0xF8, 0x00, 0xF8, 0x00, 0x78, 0x0F, 0x78, 0x0F, 0x00, 0x1F, 0x00, 0x1F]
+        );
+    }
+
+    #[test]
+    fn ping_sends_nop() {
+        let (mut display, _, log) = display(240, 320);
+        display.ping().unwrap();
+
+        assert_eq!(log.commands(), [(Command::NOP.value(), vec![])]);
+    }
+
+    #[test]
+    fn driver_state_tracking() {
+        let (mut display, mut delay, _) = display(240, 320);
+        assert!(!display.state().awake);
+
+        display.init(&mut delay).unwrap();
+        display.idle_on().unwrap().inversion_off().unwrap();
+        display.set_rotate(Rotate::Rotate90).unwrap();
+        let state = display.state();
+        assert!(state.awake && state.idle && !state.inverted);
+        assert_eq!(state.rotation, Rotate::Rotate90);
+        assert_eq!(state.mode, DisplayMode::Normal);
+        assert_eq!(state.color_format.value, 0x05);
+
+        display.sleep_in(&mut delay).unwrap();
+        assert!(!display.state().awake);
+        display.soft_reset(&mut delay).unwrap();
+        assert!(!display.state().idle);
+    }
+
+    #[test]
+    fn ramwr_settle_before_data() {
+        thread_local!(static SETTLED: Cell<u32> = const { Cell::new(0) });
+        fn wait(us: u32) {
+            SETTLED.with(|w| w.set(w.get() + us));
+        }
+
+        let (mut display, _, _) = display(240, 320);
+        display.set_ramwr_settle_us(2, wait);
+        display.display_on().unwrap();
+        assert_eq!(SETTLED.with(Cell::get), 0);
+        display.mem_write(&[0x00, 0x00]).unwrap();
+        display.pixels(0, 0, 2, 1, &mut core::iter::repeat_n(0, 2)).unwrap();
+        assert_eq!(SETTLED.with(Cell::get), 4);
+    }
+
+    #[test]
+    fn raw_image_rotated_in_software() {
+        // 3x2 image, pixel value = index
+        let data: Vec<u8> = (0..6u16).flat_map(u16::to_be_bytes).collect();
+        let drawn = |rotation| {
+            let (mut display, _, log) = display(240, 320);
+            display.draw_raw_image_rotated(0, 0, 3, 2, &data, rotation).unwrap();
+            let commands = log.commands();
+            let pixels: Vec<u16> =
+                commands[2].1.chunks(2).map(|c| u16::from_be_bytes([c[0], c[1]])).collect();
+            (commands[0].1.clone(), pixels)
+        };
+
+        assert_eq!(drawn(Rotate::Rotate0), (vec![0, 0, 0, 2], vec![0, 1, 2, 3, 4, 5]));
+        assert_eq!(drawn(Rotate::Rotate90), (vec![0, 0, 0, 1], vec![3, 0, 4, 1, 5, 2]));
+        assert_eq!(drawn(Rotate::Rotate180).1, [5, 4, 3, 2, 1, 0]);
+        assert_eq!(drawn(Rotate::Rotate270).1, [2, 5, 1, 4, 0, 3]);
+
+        let (mut display, _, _) = display(240, 320);
+        let short = display.draw_raw_image_rotated(0, 0, 3, 2, &data[..10], Rotate::Rotate90);
+        assert!(matches!(short, Err(Error::InvalidBufferSize)));
+        // rotated by 90 the image is 2 wide, 3 high; the ends overflow instead of wrapping
+        let far = display.draw_raw_image_rotated(0, u16::MAX - 1, 3, 2, &data, Rotate::Rotate90);
+        assert!(matches!(far, Err(Error::InvalidRowAddress)));
+        let far = display.draw_raw_image_rotated(u16::MAX, 0, 3, 2, &data, Rotate::Rotate0);
+        assert!(matches!(far, Err(Error::InvalidColumnAddress)));
+    }
+
+    #[test]
+    fn watchdog_kicked_between_chunks() {
+        let (mut display, _, _) = display(240, 320);
+        let frame = vec![0u8; display.frame_bytes()];
+        let mut kicks = 0;
+        display.write_framebuffer_with_kick(&frame, &mut || kicks += 1).unwrap();
+        assert_eq!(kicks, frame.len().div_ceil(4096));
+
+        let mut kicks = 0;
+        let mut colors = core::iter::repeat_n(0, 1000);
+        display.pixels_with_kick(0, 0, 100, 10, &mut colors, &mut || kicks += 1).unwrap();
+        assert_eq!(kicks, 2000usize.div_ceil(512));
+    }
+
+    #[test]
+    fn digital_gamma_enable_bit() {
+        let (mut display, _, log) = display(240, 320);
+        display.enable_digital_gamma(true).unwrap().enable_digital_gamma(false).unwrap();
+
+        assert_eq!(log.commands(), [(0xBA, vec![0x04]), (0xBA, vec![0x00])]);
+    }
+
+    #[test]
+    fn rgb444_packing() {
+        let (mut display, mut delay, log) = display(240, 320);
+        display.color_mode(ColorFormat::RGB65K_CI12Bit, &mut delay).unwrap();
+        log.clear();
+
+        // 0xF800 red, 0x07E0 green, 0x001F blue
+        display.pixels(0, 0, 2, 1, &mut [0xF800, 0x07E0].iter().copied()).unwrap();
+        assert_eq!(log.commands().last().unwrap(), &(Command::RAMWR.value(), vec![0xF0, 0x00, 0xF0]));
+
+        log.clear();
+        let mut colors = [0xF800, 0x07E0, 0x001F].iter().copied();
+        display.pixels(0, 0, 3, 1, &mut colors).unwrap();
+        assert_eq!(log.commands().last().unwrap(), &(Command::RAMWR.value(), vec![0xF0, 0x00, 0xF0, 0x00, 0xF0]));
+
+        log.clear();
+        display.pixel(1, 1, 0xFFFF).unwrap();
+        assert_eq!(log.commands().last().unwrap(), &(Command::RAMWR.value(), vec![0xFF, 0xF0]));
+
+        display.color_mode(ColorFormat::RGB65K_CI16Bit, &mut delay).unwrap();
+        log.clear();
+        display.pixel(1, 1, 0xFFFF).unwrap();
+        assert_eq!(log.commands().last().unwrap(), &(Command::RAMWR.value(), vec![0xFF, 0xFF]));
+    }
+
+    #[test]
+    fn rgb444_packs_across_odd_rows() {
+        let (mut display, mut delay, log) = display(240, 320);
+        display.color_mode(ColorFormat::RGB65K_CI12Bit, &mut delay).unwrap();
+        display.set_rotate(Rotate::Rotate180).unwrap();
+        log.clear();
+
+        let mut colors = core::iter::repeat_n(0xFFFF, 6);
+        display.pixels(0, 0, 3, 2, &mut colors).unwrap();
+        let ramwr: Vec<_> = log.commands().into_iter().filter(|c| c.0 == Command::RAMWR.value()).collect();
+        assert_eq!(ramwr, [(Command::RAMWR.value(), vec![0xFF; 9])]);
+    }
+
+    #[test]
+    fn refresh_frame_keeps_window() {
+        let (mut display, _, log) = display(240, 320);
+        display.set_window(0, 0, 2, 1).unwrap();
+        log.clear();
+        display.refresh_frame(&[1, 2, 3, 4]).unwrap().refresh_frame(&[5, 6, 7, 8]).unwrap();
+
+        assert_eq!(
+            log.commands(),
+            [(Command::RAMWR.value(), vec![1, 2, 3, 4]), (Command::RAMWR.value(), vec![5, 6, 7, 8])]
+        );
+    }
+
+    #[test]
+    fn circle_spans_and_clipping() {
+        let (mut display, _, log) = display(240, 320);
+        display.fill_circle(10, 10, 2, 0xFFFF).unwrap();
+
+        let mut rows: Vec<_> = log
+            .commands()
+            .chunks(3)
+            .map(|c| (c[1].1[1], c[0].1[1], c[0].1[3], c[2].1.len() / 2))
+            .collect();
+        rows.sort();
+        assert_eq!(rows, [(8, 9, 11, 3), (9, 8, 12, 5), (10, 8, 12, 5), (11, 8, 12, 5), (12, 9, 11, 3)]);
+
+        log.clear();
+        display.fill_circle(0, 0, 2, 0xFFFF).unwrap();
+        let widths: Vec<_> = log.commands().chunks(3).map(|c| c[2].1.len() / 2).collect();
+        assert_eq!(widths.len(), 3);
+        assert_eq!(widths.iter().sum::<usize>(), 3 + 3 + 2);
+    }
+
+    #[test]
+    fn pixels_rejects_windows_off_panel() {
+        let (mut display, _, log) = display(240, 320);
+        display.set_rotate(Rotate::Rotate90).unwrap();
+        log.clear();
+
+        let mut colors = core::iter::repeat_n(0, 20);
+        assert!(matches!(display.pixels(230, 0, 250, 1, &mut colors), Err(Error::InvalidColumnAddress)));
+        assert!(matches!(display.pixels(0, 310, 1, 330, &mut colors), Err(Error::InvalidRowAddress)));
+        assert!(log.events().is_empty());
+        assert!(display.pixels(220, 0, 240, 1, &mut colors).is_ok());
+    }
+
+    #[test]
+    fn mem_write_u16_big_endian() {
+        let (mut display, _, log) = display(240, 320);
+        display.mem_write_u16(&[0xF800, 0x1234]).unwrap();
+
+        assert_eq!(log.commands(), [(Command::RAMWR.value(), vec![0xF8, 0x00, 0x12, 0x34])]);
+    }
+
+    #[test]
+    fn color_correction_gains() {
+        let (mut display, _, log) = display(240, 320);
+        // half red, green untouched, blue doubled and clamped
+        display.set_color_correction(128, 256, 512);
+        display.pixel(0, 0, 0xFFFF).unwrap();
+        display.pixel(0, 0, 0x0841).unwrap();
+        display.fill_screen(0xFFFF).unwrap();
+
+        let ramwr: Vec<_> = log.commands().into_iter().filter(|c| c.0 == Command::RAMWR.value()).map(|c| c.1).collect();
+        assert_eq!(ramwr[0], [0x7F, 0xFF]);
+        assert_eq!(ramwr[1], [0x00, 0x42]);
+        assert_eq!(ramwr[2][..2], [0x7F, 0xFF]);
+
+        log.clear();
+        display.set_color_correction(256, 256, 256);
+        display.pixel(0, 0, 0xFFFF).unwrap();
+        assert_eq!(log.commands().last().unwrap().1, [0xFF, 0xFF]);
+    }
+
+    #[test]
+    fn refresh_rate_tuning() {
+        let (mut display, _, log) = display(240, 320);
+        let refresh = display.configure_refresh(60).unwrap();
+        assert!(refresh.millihertz.abs_diff(60_000) < 300, "{:?}", refresh);
+        let frctrl2 = log.commands().into_iter().find(|c| c.0 == Command::FRCTRL2.value()).unwrap();
+        assert_eq!(frctrl2.1, [refresh.rtna]);
+        let porch = log.commands().into_iter().find(|c| c.0 == Command::PORCTRL.value()).unwrap();
+        assert_eq!(porch.1[..2], [refresh.back_porch, refresh.front_porch]);
+
+        let fast = display.configure_refresh(115).unwrap();
+        assert_eq!(fast.rtna, 0);
+        assert!(fast.millihertz.abs_diff(115_000) < 500, "{:?}", fast);
+
+        // out of range targets end up at the limits
+        assert_eq!(display.configure_refresh(1000).unwrap().back_porch, 1);
+        assert_eq!(display.configure_refresh(1).unwrap().rtna, 31);
+    }
+
+    #[test]
+    fn erase_rect_single_window() {
+        let (mut display, _, log) = display(240, 320);
+        display.set_rotate(Rotate::Rotate180).unwrap();
+        log.clear();
+        display.erase_rect(0, 0, 10, 4).unwrap();
+
+        let commands = log.commands();
+        assert_eq!(commands.len(), 3);
+        assert_eq!(commands[1], (Command::RASET.value(), vec![0, 0, 0, 3]));
+        assert_eq!(commands[2], (Command::RAMWR.value(), vec![0; 80]));
+        assert!(matches!(display.erase_rect(0, 0, 241, 1), Err(Error::InvalidColumnAddress)));
+    }
+
+    #[test]
+    fn batch_holds_cs() {
+        let (mut display, _, log) = display(240, 320);
+        display.begin_batch().unwrap();
+        display.pixel(0, 0, 0xFFFF).unwrap();
+        display.erase_rect(0, 0, 4, 4).unwrap();
+        display.end_batch().unwrap();
+
+        let cs: Vec<_> = log.events().into_iter().filter(|e| matches!(e, Event::Cs(_))).collect();
+        assert_eq!(cs, [Event::Cs(false), Event::Cs(true)]);
+        assert_eq!(log.commands().len(), 6);
+
+        log.clear();
+        display.pixel(0, 0, 0xFFFF).unwrap();
+        assert_eq!(log.events().iter().filter(|e| **e == Event::Cs(true)).count(), 4);
+    }
+
+    #[test]
+    fn window_tracking() {
+        let (mut display, mut delay, _) = display(240, 320);
+        assert_eq!(display.debug_state().columns, None);
+
+        display.set_rotate(Rotate::Rotate90).unwrap();
+        display.pixels(0, 0, 1, 1, &mut core::iter::once(0)).unwrap();
+        let state = display.debug_state();
+        assert_eq!(state.columns, Some((0, 1)));
+        assert_eq!(state.rows, Some((0, 1)));
+        assert_eq!(state.rotation, Rotate::Rotate90);
+
+        display.soft_reset(&mut delay).unwrap();
+        assert_eq!(display.debug_state().rows, None);
+    }
+
+    #[test]
+    fn from_parts_leaves_pins_alone() {
+        let log = Log::default();
+        let display = ST7789V::from_parts(
+            MockSpi(log.clone()),
+            MockPin(log.clone(), PinKind::Cs),
+            MockPin(log.clone(), PinKind::Dc),
+            MockPin(log.clone(), PinKind::Rst),
+            240,
+            320,
+            Rotate::Rotate180,
+        )
+        .unwrap();
+
+        assert!(log.events().is_empty());
+        assert_eq!(display.state().rotation, Rotate::Rotate180);
+    }
+
+    #[test]
+    fn blit_logical_single_window() {
+        let (mut display, _, log) = display(240, 320);
+        display.set_rotate(Rotate::Rotate270).unwrap();
+        log.clear();
+        display.blit_logical(&[1, 2, 3, 4, 5, 6], 3, 2).unwrap();
+
+        let commands = log.commands();
+        assert_eq!(commands.len(), 3);
+        assert_eq!(commands[0], (Command::CASET.value(), vec![0, 0, 0, 2]));
+        assert_eq!(commands[1], (Command::RASET.value(), vec![0, 0, 0, 1]));
+        assert_eq!(commands[2], (Command::RAMWR.value(), vec![0, 1, 0, 2, 0, 3, 0, 4, 0, 5, 0, 6]));
+        assert!(matches!(display.blit_logical(&[0; 5], 3, 2), Err(Error::InvalidBufferSize)));
+    }
+
+    #[test]
+    fn send_typed_command() {
+        let (mut display, _, log) = display(240, 320);
+        display.send(Command::GATECTRL, Some(&[0x27, 0x00, 0x10])).unwrap().send(Command::SLPOUT, None).unwrap();
+
+        assert_eq!(log.commands(), [(0xE4, vec![0x27, 0x00, 0x10]), (0x11, vec![])]);
+        assert!(display.state().awake);
+    }
+
+    #[test]
+    fn row_offset_for_240x240() {
+        let (mut display, _, log) = display(240, 240);
+        assert!(matches!(display.set_row_offset(81), Err(Error::InvalidRowAddress)));
+        display.set_flip(Flip::Vertical).unwrap().set_row_offset(crate::ROW_OFFSET_240X240).unwrap();
+        log.clear();
+        display.pixel(0, 0, 0).unwrap();
+        display.pixel(0, 239, 0).unwrap();
+
+        let rasets: Vec<_> = log.commands().into_iter().filter(|c| c.0 == Command::RASET.value()).map(|c| c.1).collect();
+        assert_eq!(rasets, [vec![0, 80, 0, 80], vec![0x01, 0x3F, 0x01, 0x3F]]);
+    }
+
+    #[test]
+    fn exclusive_never_touches_cs() {
+        let log = Log::default();
+        let mut display = ST7789V::exclusive(
+            MockSpi(log.clone()),
+            MockPin(log.clone(), PinKind::Dc),
+            MockPin(log.clone(), PinKind::Rst),
+            240,
+            320,
+        );
+        display.display_on().unwrap();
+
+        assert!(!log.events().iter().any(|e| matches!(e, Event::Cs(_))));
+        assert_eq!(log.bytes(), [Command::DISPON.value()]);
+    }
+
+    #[test]
+    fn cs_setup_delay_after_select() {
+        thread_local!(static WAITED: Cell<u32> = const { Cell::new(0) });
+        fn wait(us: u32) {
+            WAITED.with(|w| w.set(w.get() + us));
+        }
+
+        let (mut display, _, _) = display(240, 320);
+        display.set_cs_setup_delay_us(3, wait);
+        display.display_on().unwrap();
+        assert_eq!(WAITED.with(Cell::get), 3);
+
+        display.set_cs_setup_delay_us(0, wait);
+        display.display_on().unwrap();
+        assert_eq!(WAITED.with(Cell::get), 3);
+    }
+
+    #[test]
+    fn init_uses_configured_vcom() {
+        let (mut display, mut delay, log) = display(240, 320);
+        display.set_vcom(0x50).unwrap();
+        assert_eq!(log.commands(), [(Command::VCOMS.value(), vec![0x3F])]);
+
+        log.clear();
+        display.set_vcom(0x2B).unwrap();
+        display.init_no_reset(&mut delay).unwrap();
+        let vcoms: Vec<_> = log
+            .commands()
+            .into_iter()
+            .filter(|(cmd, _)| *cmd == Command::VCOMS.value())
+            .collect();
+        assert_eq!(vcoms, vec![(Command::VCOMS.value(), vec![0x2B]); 2]);
+    }
+
+    #[test]
+    fn init_no_reset_leaves_rst_alone() {
+        let (mut display, mut delay, log) = display(240, 320);
+        display.init_no_reset(&mut delay).unwrap();
+
+        assert!(!log.events().iter().any(|e| matches!(e, Event::Rst(_))));
+        assert_eq!(log.bytes()[..2], [0x36, 0x00]);
+    }
+
+    #[test]
+    fn power_on_order() {
+        let (mut display, mut delay, log) = display(240, 320);
+        let mut backlight = MockPin(log.clone(), PinKind::Bl);
+        display.power_on(&mut delay, &mut backlight).unwrap();
+
+        let events = log.events();
+        let at = |event: Event| events.iter().position(|e| *e == event).unwrap();
+        let slpout = at(Event::Write(vec![Command::SLPOUT.value()]));
+        let dispon = at(Event::Write(vec![Command::DISPON.value()]));
+        assert!(at(Event::Rst(true)) < slpout && slpout < dispon);
+        // the panel settles after SLPOUT before it is switched on
+        assert!(events[slpout..dispon].contains(&Event::Delay(120)));
+        // backlight last, once, after the delays of init
+        assert_eq!(events.iter().filter(|e| matches!(e, Event::Bl(_))).count(), 1);
+        assert_eq!(events.last().unwrap(), &Event::Bl(true));
+        assert_eq!(log.delay_ms(), crate::INIT_DELAY_MS);
+    }
+
+    #[test]
+    fn power_off_order() {
+        let (mut display, mut delay, log) = display(240, 320);
+        let mut backlight = MockPin(log.clone(), PinKind::Bl);
+        display.power_off(&mut delay, &mut backlight).unwrap();
+
+        let events = log.events();
+        assert_eq!(events[0], Event::Bl(false));
+        assert_eq!(
+            log.commands(),
+            [(Command::DISPOFF.value(), vec![]), (Command::SLPIN.value(), vec![])]
+        );
+        assert_eq!(events.last().unwrap(), &Event::Delay(5));
+        assert_eq!(events.iter().filter(|e| matches!(e, Event::Bl(_))).count(), 1);
+    }
+
+    #[test]
+    fn hard_reset_us_pulse() {
+        let (mut display, mut delay, log) = display(240, 320);
+        display.hard_reset_us(&mut delay).unwrap();
+
+        assert_eq!(
+            log.events(),
+            [
+                Event::Cs(true),
+                Event::Rst(false),
+                Event::DelayUs(10),
+                Event::Rst(true),
+                Event::DelayUs(120_000),
+            ]
+        );
+    }
+
+    #[test]
+    fn init_and_clear_fills_before_display_on() {
+        let (mut display, mut delay, log) = display(240, 320);
+        display.init_and_clear(0x0000, &mut delay).unwrap();
+
+        let commands = log.commands();
+        let n = commands.len();
+        assert_eq!(commands[n - 2].0, Command::RAMWR.value());
+        assert_eq!(commands[n - 2].1.len(), 240 * 320 * 2);
+        assert!(commands[n - 2].1.iter().all(|&b| b == 0));
+        assert_eq!(commands[n - 1], (Command::DISPON.value(), vec![]));
+        assert_eq!(commands.iter().filter(|c| c.0 == Command::DISPON.value()).count(), 1);
+    }
+
+    #[test]
+    fn set_window_without_ramwr() {
+        let (mut display, _, log) = display(240, 320);
+        display.set_window(10, 20, 30, 40).unwrap();
+
+        assert_eq!(
+            log.commands(),
+            [
+                (Command::CASET.value(), vec![0, 10, 0, 29]),
+                (Command::RASET.value(), vec![0, 20, 0, 39]),
+            ]
+        );
+        assert!(display.set_window(30, 0, 10, 1).is_err());
+    }
+
+    #[test]
+    fn address_window_full_panel() {
+        let (mut display, _, log) = display(240, 320);
+        display.address_window(0, 0, 240, 320).unwrap();
+
+        assert_eq!(
+            log.commands(),
+            [
+                (Command::CASET.value(), vec![0x00, 0x00, 0x00, 0xEF]),
+                (Command::RASET.value(), vec![0x00, 0x00, 0x01, 0x3F]),
+                (Command::RAMWR.value(), vec![]),
+            ]
+        );
+    }
+
+    #[test]
+    fn address_window_end_boundaries() {
+        let (mut display, _, log) = display(240, 320);
+
+        assert!(matches!(
+            display.address_window(0, 0, 0, 320),
+            Err(Error::InvalidColumnAddress)
+        ));
+        assert!(matches!(
+            display.address_window(0, 0, 240, 0),
+            Err(Error::InvalidRowAddress)
+        ));
+        assert!(log.bytes().is_empty());
+
+        display.address_window(0, 0, 1, 1).unwrap();
+        display.address_window(0, 0, 240, 320).unwrap();
+        let commands = log.commands();
+        assert_eq!(commands[0], (Command::CASET.value(), vec![0x00, 0x00, 0x00, 0x00]));
+        assert_eq!(commands[3], (Command::CASET.value(), vec![0x00, 0x00, 0x00, 0xEF]));
+    }
+
+    #[test]
+    fn empty_windows_rejected() {
+        let (mut display, _, log) = display(240, 320);
+
+        assert!(matches!(display.set_window(5, 0, 5, 10), Err(Error::InvalidColumnAddress)));
+        assert!(matches!(display.set_window(0, 5, 10, 5), Err(Error::InvalidRowAddress)));
+        assert!(matches!(display.erase_rect(5, 5, 5, 10), Err(Error::InvalidColumnAddress)));
+        assert!(matches!(
+            display.fill_gradient(5, 5, 5, 5, 0xFFFF, 0),
+            Err(Error::InvalidColumnAddress)
+        ));
+        assert!(log.bytes().is_empty());
+
+        // one past the start is the smallest window
+        display.set_window(0, 0, 1, 1).unwrap();
+        assert!(matches!(display.set_window(1, 0, 1, 1), Err(Error::InvalidColumnAddress)));
+        assert_eq!(
+            log.commands(),
+            [
+                (Command::CASET.value(), vec![0, 0, 0, 0]),
+                (Command::RASET.value(), vec![0, 0, 0, 0]),
+            ]
+        );
+    }
+
+    #[test]
+    fn address_window_rejects_inverted_window() {
+        let (mut display, _, log) = display(240, 320);
+
+        assert!(matches!(
+            display.address_window(10, 0, 9, 320),
+            Err(Error::InvalidColumnAddress)
+        ));
+        assert!(log.bytes().is_empty());
+    }
+
+    #[test]
+    fn gate_control_params() {
+        let (mut display, _, log) = display(240, 240);
+        display.set_gate_control(0x1D, 0x00, crate::GATECTRL_GS | 0xC0).unwrap();
+
+        assert_eq!(log.commands(), [(Command::GATECTRL.value(), vec![0x1D, 0x00, 0x01])]);
+    }
+
+    #[test]
+    fn vrh_vdv_enable_and_clamp() {
+        let (mut display, _, log) = display(240, 320);
+        display.set_vrh(0x30).unwrap().set_vdv(0x50).unwrap();
+
+        assert_eq!(
+            log.commands(),
+            [
+                (Command::VDVVRHEN.value(), vec![0x01]),
+                (Command::VRHS.value(), vec![0x27]),
+                (Command::VDVS.value(), vec![0x20]),
+                (Command::VDVVRHEN.value(), vec![0x01]),
+                (Command::VRHS.value(), vec![0x27]),
+                (Command::VDVS.value(), vec![0x3F]),
+            ]
+        );
+    }
+
+    #[test]
+    fn lcm_control_bytes() {
+        let (mut display, _, log) = display(240, 320);
+        display.lcm_control().unwrap();
+
+        assert_eq!(
+            log.commands(),
+            [
+                (Command::LCMCTRL.value(), vec![0x2C]),
+                (Command::VDVVRHEN.value(), vec![0x01]),
+                (Command::VRHS.value(), vec![0x12]),
+                (Command::VDVS.value(), vec![0x20]),
+                (Command::FRCTRL2.value(), vec![0x0F]),
+                (Command::PWCTRL1.value(), vec![0xA4, 0xA1]),
+                (Command::UNKNOWN_D6.value(), vec![0xA1]),
+            ]
+        );
+    }
+
+    #[test]
+    fn power_voltages_shared_by_init_and_lcm_control() {
+        let (mut display, mut delay, log) = display(240, 320);
+        display.set_vrh(0x14).unwrap();
+        log.clear();
+        display.lcm_control().unwrap();
+        let lcm = log.commands();
+        log.clear();
+        display.init(&mut delay).unwrap();
+        let init = log.commands();
+
+        for cmd in [Command::VRHS, Command::VDVS] {
+            let sent = |log: &[(u8, Vec<u8>)]| log.iter().find(|c| c.0 == cmd.value()).cloned();
+            assert_eq!(sent(&lcm), sent(&init));
+        }
+        assert!(init.contains(&(Command::VRHS.value(), vec![0x14])));
+    }
+
+    #[test]
+    fn mono_bitmap_expands_bits() {
+        let (mut display, _, log) = display(240, 320);
+        // 10 pixels wide, so each row is padded to two bytes
+        let bits = [0b1000_0000, 0b0100_0000, 0b0000_0001, 0b1111_1111];
+        display.draw_mono_bitmap(0, 0, 10, 2, &bits, 0xFFFF, 0x0000).unwrap();
+
+        let commands = log.commands();
+        let (cmd, data) = commands.last().unwrap();
+        assert_eq!(*cmd, Command::RAMWR.value());
+        let pixels: Vec<u16> = data.chunks(2).map(|c| u16::from_be_bytes([c[0], c[1]])).collect();
+        assert_eq!(
+            pixels,
+            [
+                0xFFFF, 0, 0, 0, 0, 0, 0, 0, 0, 0xFFFF,
+                0, 0, 0, 0, 0, 0, 0, 0xFFFF, 0xFFFF, 0xFFFF,
+            ]
+        );
+
+        log.clear();
+        let short = display.draw_mono_bitmap(0, 0, 10, 2, &bits[..3], 0xFFFF, 0x0000);
+        assert!(matches!(short, Err(Error::InvalidBufferSize)));
+        let far = display.draw_mono_bitmap(0, u16::MAX, 10, 2, &bits, 0xFFFF, 0x0000);
+        assert!(matches!(far, Err(Error::InvalidRowAddress)));
+        assert!(log.commands().is_empty());
+    }
+
+    #[test]
+    fn cabc_mode_keeps_color_enhancement() {
+        let (mut display, _, log) = display(240, 320);
+        display.write_cabc(0b1011_0001).unwrap();
+        log.clear();
+        display.set_cabc(CabcMode::MovingImage).unwrap();
+
+        assert_eq!(log.commands(), [(Command::WRCACE.value(), vec![0b1011_0011])]);
+    }
+
+    #[test]
+    fn dimming_keeps_backlight_bit() {
+        let (mut display, _, log) = display(240, 320);
+        display.write_ctrl_display(false, false, true).unwrap();
+        display.enable_display_dimming(true).unwrap();
+        display.enable_display_dimming(false).unwrap();
+        display.write_ctrl_display(true, true, false).unwrap();
+
+        assert_eq!(
+            log.commands(),
+            [
+                (Command::WRCTRLD.value(), vec![0b0000_0100]),
+                (Command::WRCTRLD.value(), vec![0b0010_1100]),
+                // BCTRL stays on, only DD goes off
+                (Command::WRCTRLD.value(), vec![0b0010_0100]),
+                (Command::WRCTRLD.value(), vec![0b0010_1000]),
+            ]
+        );
+        assert_eq!(log.bytes()[..2], [0x53, 0x04]);
+    }
+
+    #[test]
+    fn cabc_min_brightness_byte() {
+        let (mut display, _, log) = display(240, 320);
+        display.cabc_min_brightness(0x40).unwrap().cabc_min_brightness(0xFF).unwrap();
+
+        assert_eq!(log.bytes(), [0x5E, 0x40, 0x5E, 0xFF]);
+    }
+
+    #[test]
+    fn color_enhancement_keeps_cabc() {
+        let (mut display, _, log) = display(240, 320);
+        display.set_cabc(CabcMode::StillPicture).unwrap();
+        log.clear();
+        display.set_color_enhancement(2).unwrap().set_color_enhancement(9).unwrap();
+        display.set_color_enhancement(0).unwrap();
+
+        assert_eq!(
+            log.commands(),
+            [
+                (Command::WRCACE.value(), vec![0b1001_0010]),
+                (Command::WRCACE.value(), vec![0b1011_0010]),
+                (Command::WRCACE.value(), vec![0b0000_0010]),
+            ]
+        );
+    }
+
+    #[test]
+    fn madctl_table_matches_configs() {
+        use crate::madctl_for;
+
+        assert_eq!(madctl_for(Rotate::Rotate0), MemAccCtrlConfig::rotate_0().value());
+        assert_eq!(madctl_for(Rotate::Rotate90), MemAccCtrlConfig::rotate_90().value());
+        assert_eq!(madctl_for(Rotate::Rotate180), MemAccCtrlConfig::rotate_180().value());
+        assert_eq!(madctl_for(Rotate::Rotate270), MemAccCtrlConfig::rotate_270().value());
+    }
+
+    #[test]
+    fn rotated_pixels_stream_unchanged() {
+        let (mut plain, _, log) = display(240, 320);
+        plain.pixels(0, 0, 20, 30, &mut (0..600u16)).unwrap();
+        let expected = log.commands();
+
+        let (mut rotated, _, log) = display(240, 320);
+        rotated.set_rotate(Rotate::Rotate270).unwrap();
+        log.clear();
+        rotated.pixels(0, 0, 20, 30, &mut (0..600u16)).unwrap();
+        // MADCTL does the rotating, window and pixel order stay as drawn
+        assert_eq!(log.commands(), expected);
+        assert_eq!(expected.len(), 3);
+    }
+
+    #[test]
+    fn porch_params() {
+        let (mut display, _, log) = display(240, 320);
+        display.porch_setting().unwrap().set_porch(0x8C, 0x02, true, 0x22, 0x11).unwrap();
+
+        assert_eq!(
+            log.commands(),
+            [
+                (Command::PORCTRL.value(), vec![0x0C, 0x0C, 0x00, 0x33, 0x33]),
+                (Command::PORCTRL.value(), vec![0x0C, 0x02, 0x01, 0x22, 0x11]),
+            ]
+        );
+    }
+
+    #[test]
+    fn write_rows_pads_and_cuts() {
+        let (mut display, _, log) = display(240, 320);
+        let rows: [&[u16]; 2] = [&[1, 2, 3], &[4]];
+        display.write_rows(0, 10, 2, rows.iter().copied()).unwrap();
+
+        let commands = log.commands();
+        assert_eq!(commands[1], (Command::RASET.value(), vec![0x00, 0x0A, 0x01, 0x3F]));
+        assert_eq!(commands.last().unwrap().1, [0, 1, 0, 2, 0, 4, 0, 0]);
+
+        // only the 2 rows left above the bottom edge are sent
+        log.clear();
+        display.write_rows(0, 318, 1, core::iter::repeat(&[7u16][..])).unwrap();
+        assert_eq!(log.commands().last().unwrap().1, [0, 7, 0, 7]);
+        let far = display.write_rows(u16::MAX, 0, 2, rows.iter().copied());
+        assert!(matches!(far, Err(Error::InvalidColumnAddress)));
+
+        log.clear();
+        let wide = display.write_rows(300, 0, 100, rows.iter().copied());
+        assert!(matches!(wide, Err(Error::InvalidColumnAddress)));
+        let past_edge = display.write_rows(239, 0, 2, rows.iter().copied());
+        assert!(matches!(past_edge, Err(Error::InvalidColumnAddress)));
+        let below = display.write_rows(0, 320, 2, rows.iter().copied());
+        assert!(matches!(below, Err(Error::InvalidRowAddress)));
+        assert!(log.bytes().is_empty());
+        assert!(display.write_rows(238, 319, 2, rows.iter().copied()).is_ok());
+    }
+
+    #[test]
+    fn raw_image_byte_order() {
+        let (mut display, _, log) = display(240, 320);
+        display.write_raw_image_le(0, 0, 2, 1, &[0x00, 0xF8, 0xE0, 0x07]).unwrap();
+        display.write_raw_image_be(0, 0, 2, 1, &[0xF8, 0x00, 0x07, 0xE0]).unwrap();
+
+        let commands = log.commands();
+        assert_eq!(commands[2], (Command::RAMWR.value(), vec![0xF8, 0x00, 0x07, 0xE0]));
+        assert_eq!(commands[5], commands[2]);
+
+        log.clear();
+        let far = display.write_raw_image(u16::MAX, 0, 2, 1, &[0; 4]);
+        assert!(matches!(far, Err(Error::InvalidColumnAddress)));
+        let far = display.write_raw_image_le(0, u16::MAX, 2, 1, &[0; 4]);
+        assert!(matches!(far, Err(Error::InvalidRowAddress)));
+        assert!(log.commands().is_empty());
+    }
+
+    #[test]
+    fn test_pattern_bars() {
+        let (mut display, _, log) = display(240, 320);
+        display.draw_test_pattern().unwrap();
+
+        let commands = log.commands();
+        let columns: Vec<&Vec<u8>> = commands
+            .iter()
+            .filter(|(cmd, _)| *cmd == Command::CASET.value())
+            .map(|(_, params)| params)
+            .collect();
+        assert_eq!(columns.len(), 5);
+        assert_eq!(columns[0], &vec![0, 0, 0, 47]);
+        assert_eq!(columns[4], &vec![0, 192, 0, 239]);
+        let first = commands.iter().find(|(cmd, _)| *cmd == Command::RAMWR.value()).unwrap();
+        assert_eq!(first.1.len(), 48 * 320 * 2);
+        assert_eq!(first.1[..2], [0xF8, 0x00]);
+    }
+
+    #[test]
+    fn framebuffer_size() {
+        let (mut display, mut delay, log) = display(240, 320);
+        assert_eq!(display.frame_bytes(), 240 * 320 * 2);
+        assert!(matches!(display.write_framebuffer(&[0; 100]), Err(Error::InvalidBufferSize)));
+        assert!(log.events().is_empty());
+
+        display.write_framebuffer(&vec![0; 240 * 320 * 2]).unwrap();
+        display.color_mode(ColorFormat::RGB65K_CI12Bit, &mut delay).unwrap();
+        assert_eq!(display.frame_bytes(), 240 * 320 * 3 / 2);
+        display.color_mode(ColorFormat::RGB65K_CI18Bit, &mut delay).unwrap();
+        assert_eq!(display.frame_bytes(), 240 * 320 * 3);
+    }
+
+    #[test]
+    fn pixel_bounds() {
+        let (mut display, _, log) = display(240, 320);
+
+        assert!(matches!(display.pixel(240, 0, 0), Err(Error::InvalidColumnAddress)));
+        assert!(matches!(display.pixel(0, 320, 0), Err(Error::InvalidRowAddress)));
+        assert!(log.events().is_empty());
+        assert!(display.pixel(239, 319, 0).is_ok());
+    }
+
+    #[test]
+    fn pixel_at_origin_is_one_pixel_window() {
+        let (mut display, _, log) = display(240, 320);
+        for rotation in [Rotate::Rotate0, Rotate::Rotate90, Rotate::Rotate180, Rotate::Rotate270] {
+            display.set_rotate(rotation).unwrap();
+            log.clear();
+            display.pixel(0, 0, 0xFFFF).unwrap();
+
+            let commands = log.commands();
+            assert_eq!(commands[0], (Command::CASET.value(), vec![0, 0, 0, 0]));
+            assert_eq!(commands[1], (Command::RASET.value(), vec![0, 0, 0, 0]));
+        }
+        assert!(matches!(display.column_address(0, 0), Err(Error::InvalidColumnAddress)));
+        assert!(matches!(display.row_address(0, 0), Err(Error::InvalidRowAddress)));
+    }
+
+    #[test]
+    fn rotated_pixel_stays_on_panel() {
+        for rotation in [Rotate::Rotate90, Rotate::Rotate180, Rotate::Rotate270] {
+            let (mut display, _, log) = display(240, 320);
+            display.set_rotate(rotation).unwrap();
+            log.clear();
+            display.pixel(239, 319, 0xFFFF).unwrap();
+
+            let commands = log.commands();
+            assert_eq!(commands[0], (Command::CASET.value(), vec![0, 239, 0, 239]));
+            assert_eq!(commands[1], (Command::RASET.value(), vec![0x01, 0x3F, 0x01, 0x3F]));
+
+            log.clear();
+            display.pixels(239, 319, 240, 320, &mut core::iter::once(0xFFFF)).unwrap();
+            assert_eq!(log.commands()[..2], commands[..2]);
+        }
+    }
+
+    #[test]
+    fn pixel_byte_stream() {
+        let (mut display, _, log) = display(240, 320);
+        display.pixel(10, 20, 0xF800).unwrap();
+
+        let commands = log.commands();
+        assert_eq!(commands[0], (Command::CASET.value(), vec![0x00, 0x0A, 0x00, 0x0A]));
+        assert_eq!(commands[1], (Command::RASET.value(), vec![0x00, 0x14, 0x00, 0x14]));
+        assert_eq!(commands.last().unwrap(), &(Command::RAMWR.value(), vec![0xF8, 0x00]));
+    }
+
+    #[test]
+    fn set_rotate_writes_madctl() {
+        use crate::madctl_for;
+
+        let (mut display, _, log) = display(240, 320);
+        display.set_rotate(Rotate::Rotate90).unwrap();
+        display.set_flip(Flip::Horizontal).unwrap();
+        display.set_rotate(Rotate::Rotate180).unwrap();
+
+        let madctl = |value| (Command::MADCTL.value(), vec![value]);
+        assert_eq!(
+            log.commands(),
+            [
+                madctl(madctl_for(Rotate::Rotate90)),
+                madctl(madctl_for(Rotate::Rotate90) ^ Flip::Horizontal.value()),
+                madctl(madctl_for(Rotate::Rotate180) ^ Flip::Horizontal.value()),
+            ]
+        );
+        assert_eq!(display.debug_state().madctl, madctl_for(Rotate::Rotate180) ^ 0x40);
+    }
 }
@@ -0,0 +1,288 @@
+//! Mock SPI bus, pins and delay recording everything the driver does, for unit tests.
+
+use core::convert::Infallible;
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+use embedded_hal::blocking::delay::{DelayMs, DelayUs};
+use embedded_hal::blocking::spi;
+use embedded_hal::digital::v2::{InputPin, OutputPin};
+
+use crate::ST7789V;
+
+/// Something the driver did to the bus, a pin or the delay
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    /// Chip select pin level
+    Cs(bool),
+    /// Data/command pin level
+    Dc(bool),
+    /// Reset pin level
+    Rst(bool),
+    /// Backlight pin level
+    Bl(bool),
+    /// Tearing effect pin level as sampled by the driver
+    Te(bool),
+    /// Bytes written to the SPI bus
+    Write(Vec<u8>),
+    /// 16 bit words written to the SPI bus
+    Write16(Vec<u16>),
+    /// Bytes read back from the SPI bus
+    Read(Vec<u8>),
+    /// Delay in milliseconds
+    Delay(u16),
+    /// Delay in microseconds
+    DelayUs(u32),
+}
+
+/// Shared event log, plus the bytes the mock bus answers reads with
+#[derive(Debug, Clone, Default)]
+pub struct Log(Rc<RefCell<Vec<Event>>>, Rc<RefCell<VecDeque<u8>>>);
+
+impl Log {
+    fn push(&self, event: Event) {
+        self.0.borrow_mut().push(event);
+    }
+
+    /// Queue bytes to be returned by the next reads. Reads past the queue return 0xFF.
+    pub fn respond(&self, bytes: &[u8]) {
+        self.1.borrow_mut().extend(bytes);
+    }
+
+    /// All recorded events
+    pub fn events(&self) -> Vec<Event> {
+        self.0.borrow().clone()
+    }
+
+    /// Forget everything recorded so far
+    pub fn clear(&self) {
+        self.0.borrow_mut().clear();
+    }
+
+    /// All bytes written to the SPI bus, regardless of the DC level
+    pub fn bytes(&self) -> Vec<u8> {
+        self.0
+            .borrow()
+            .iter()
+            .filter_map(|e| match e {
+                Event::Write(bytes) => Some(bytes.clone()),
+                _ => None,
+            })
+            .flatten()
+            .collect()
+    }
+
+    /// Writes decoded by the DC level as `(command, parameters)`
+    pub fn commands(&self) -> Vec<(u8, Vec<u8>)> {
+        let mut dc = false;
+        let mut commands: Vec<(u8, Vec<u8>)> = Vec::new();
+        for event in self.0.borrow().iter() {
+            match event {
+                Event::Dc(level) => dc = *level,
+                Event::Write(bytes) if dc => {
+                    if let Some((_, params)) = commands.last_mut() {
+                        params.extend_from_slice(bytes);
+                    }
+                }
+                Event::Write(bytes) => commands.extend(bytes.iter().map(|&b| (b, Vec::new()))),
+                _ => {}
+            }
+        }
+        commands
+    }
+
+    /// Sum of all recorded delays in milliseconds
+    pub fn delay_ms(&self) -> u32 {
+        self.0
+            .borrow()
+            .iter()
+            .map(|e| match e {
+                Event::Delay(ms) => *ms as u32,
+                _ => 0,
+            })
+            .sum()
+    }
+}
+
+/// Mock SPI bus
+pub struct MockSpi(pub Log);
+
+impl spi::Write<u8> for MockSpi {
+    type Error = Infallible;
+
+    fn write(&mut self, words: &[u8]) -> Result<(), Self::Error> {
+        self.0.push(Event::Write(words.to_vec()));
+        Ok(())
+    }
+}
+
+impl spi::Write<u16> for MockSpi {
+    type Error = Infallible;
+
+    fn write(&mut self, words: &[u16]) -> Result<(), Self::Error> {
+        self.0.push(Event::Write16(words.to_vec()));
+        Ok(())
+    }
+}
+
+impl spi::Transfer<u8> for MockSpi {
+    type Error = Infallible;
+
+    fn transfer<'w>(&mut self, words: &'w mut [u8]) -> Result<&'w [u8], Self::Error> {
+        for word in words.iter_mut() {
+            *word = self.0 .1.borrow_mut().pop_front().unwrap_or(0xFF);
+        }
+        self.0.push(Event::Read(words.to_vec()));
+        Ok(words)
+    }
+}
+
+/// Mock SPI bus whose writes fail while the shared counter is above zero, counting it down
+pub struct FlakySpi(pub Log, pub Rc<Cell<u8>>);
+
+impl spi::Write<u8> for FlakySpi {
+    type Error = ();
+
+    fn write(&mut self, words: &[u8]) -> Result<(), Self::Error> {
+        let failures = self.1.get();
+        if failures > 0 {
+            self.1.set(failures - 1);
+            return Err(());
+        }
+        self.0.push(Event::Write(words.to_vec()));
+        Ok(())
+    }
+}
+
+/// Bus whose byte writes always go through and whose next `.1` word writes fail, for
+/// retries of the 16 bit data path
+pub struct FlakyWords(pub Log, pub Rc<Cell<u8>>);
+
+impl spi::Write<u8> for FlakyWords {
+    type Error = ();
+
+    fn write(&mut self, words: &[u8]) -> Result<(), Self::Error> {
+        self.0.push(Event::Write(words.to_vec()));
+        Ok(())
+    }
+}
+
+impl spi::Write<u16> for FlakyWords {
+    type Error = ();
+
+    fn write(&mut self, words: &[u16]) -> Result<(), Self::Error> {
+        let failures = self.1.get();
+        if failures > 0 {
+            self.1.set(failures - 1);
+            return Err(());
+        }
+        self.0.push(Event::Write16(words.to_vec()));
+        Ok(())
+    }
+}
+
+/// Which pin a `MockPin` is
+#[derive(Debug, Clone, Copy)]
+pub enum PinKind {
+    Cs,
+    Dc,
+    Rst,
+    Bl,
+}
+
+/// Mock output pin
+pub struct MockPin(pub Log, pub PinKind);
+
+impl MockPin {
+    fn set(&mut self, level: bool) {
+        self.0.push(match self.1 {
+            PinKind::Cs => Event::Cs(level),
+            PinKind::Dc => Event::Dc(level),
+            PinKind::Rst => Event::Rst(level),
+            PinKind::Bl => Event::Bl(level),
+        });
+    }
+}
+
+impl OutputPin for MockPin {
+    type Error = Infallible;
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.set(false);
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.set(true);
+        Ok(())
+    }
+}
+
+/// Mock TE input, returning a scripted sequence of levels (low once it runs out)
+pub struct MockTe(Log, RefCell<VecDeque<bool>>);
+
+impl MockTe {
+    /// Pin that reads `levels` in order, one per sample
+    pub fn new(log: Log, levels: &[bool]) -> Self {
+        MockTe(log, RefCell::new(levels.iter().copied().collect()))
+    }
+
+    /// Number of scripted levels not sampled yet
+    pub fn remaining(&self) -> usize {
+        self.1.borrow().len()
+    }
+
+    fn sample(&self) -> bool {
+        let level = self.1.borrow_mut().pop_front().unwrap_or(false);
+        self.0.push(Event::Te(level));
+        level
+    }
+}
+
+impl InputPin for MockTe {
+    type Error = Infallible;
+
+    fn is_high(&self) -> Result<bool, Self::Error> {
+        Ok(self.sample())
+    }
+
+    fn is_low(&self) -> Result<bool, Self::Error> {
+        Ok(!self.sample())
+    }
+}
+
+/// Mock delay
+pub struct MockDelay(pub Log);
+
+impl DelayMs<u16> for MockDelay {
+    fn delay_ms(&mut self, ms: u16) {
+        self.0.push(Event::Delay(ms));
+    }
+}
+
+impl DelayUs<u32> for MockDelay {
+    fn delay_us(&mut self, us: u32) {
+        self.0.push(Event::DelayUs(us));
+    }
+}
+
+/// Driver over the mock bus
+pub type MockDisplay = ST7789V<MockSpi, MockPin, MockPin, MockPin, Infallible, Infallible>;
+
+/// Create a display with chip select over a fresh log. The log is empty on return.
+pub fn display(width: u16, height: u16) -> (MockDisplay, MockDelay, Log) {
+    let log = Log::default();
+    let display = ST7789V::with_cs(
+        MockSpi(log.clone()),
+        MockPin(log.clone(), PinKind::Cs),
+        MockPin(log.clone(), PinKind::Dc),
+        MockPin(log.clone(), PinKind::Rst),
+        width,
+        height,
+    )
+    .unwrap();
+    log.clear();
+
+    (display, MockDelay(log.clone()), log)
+}
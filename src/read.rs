@@ -0,0 +1,463 @@
+//! Register reads, for buses that can read back (MISO/SDO wired).
+//!
+//! Most modules (including the Waveshare 2inch one) do not bring out SDO, on those every
+//! read returns whatever the floating MISO line happens to be.
+
+use embedded_hal::blocking::spi;
+use embedded_hal::digital::v2::OutputPin;
+
+use crate::command::Command;
+use crate::{madctl_for, Error, Flip, ST7789V};
+
+/// Display power mode as reported by RDDPM
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PowerMode {
+    /// Booster voltage status (D7)
+    pub booster_on: bool,
+    /// Idle mode on (D6)
+    pub idle: bool,
+    /// Partial mode on (D5)
+    pub partial: bool,
+    /// Sleep out (D4)
+    pub sleep_out: bool,
+    /// Normal display mode on (D3)
+    pub normal: bool,
+    /// Display on (D2)
+    pub display_on: bool,
+}
+
+impl PowerMode {
+    /// Parse the RDDPM byte
+    pub fn from_value(value: u8) -> Self {
+        PowerMode {
+            booster_on: value & 0b1000_0000 != 0,
+            idle: value & 0b0100_0000 != 0,
+            partial: value & 0b0010_0000 != 0,
+            sleep_out: value & 0b0001_0000 != 0,
+            normal: value & 0b0000_1000 != 0,
+            display_on: value & 0b0000_0100 != 0,
+        }
+    }
+}
+
+/// Memory data access control as reported by RDDMADCTL
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Madctl {
+    /// Raw register value, comparable with `MemAccCtrlConfig::value`
+    pub value: u8,
+    /// Page address order, bottom to top (MY)
+    pub page_bottom_to_top: bool,
+    /// Column address order, `ColumnAddressOrder::LeftToRight` (MX)
+    pub column_left_to_right: bool,
+    /// Page/column reverse mode (MV)
+    pub page_column_reverse: bool,
+    /// Line address order, bottom to top (ML)
+    pub line_bottom_to_top: bool,
+    /// BGR color order (RGB)
+    pub bgr: bool,
+    /// Display data latch order, right to left (MH)
+    pub latch_right_to_left: bool,
+}
+
+impl Madctl {
+    /// Parse the RDDMADCTL byte
+    pub fn from_value(value: u8) -> Self {
+        Madctl {
+            value,
+            page_bottom_to_top: value & 0b1000_0000 != 0,
+            column_left_to_right: value & 0b0100_0000 != 0,
+            page_column_reverse: value & 0b0010_0000 != 0,
+            line_bottom_to_top: value & 0b0001_0000 != 0,
+            bgr: value & 0b0000_1000 != 0,
+            latch_right_to_left: value & 0b0000_0100 != 0,
+        }
+    }
+}
+
+/// Interface pixel format as reported by RDDCOLMOD
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PixelFormat {
+    /// Raw register value, comparable with `ColorFormat::value`
+    pub value: u8,
+    /// RGB interface color format (D6..D4), 0b101 65K, 0b110 262K
+    pub rgb_interface: u8,
+    /// Control interface color format (D2..D0), 0b011 12 bit, 0b101 16 bit, 0b110 18 bit
+    pub control_interface: u8,
+}
+
+impl PixelFormat {
+    /// Parse the RDDCOLMOD byte
+    pub fn from_value(value: u8) -> Self {
+        PixelFormat {
+            value,
+            rgb_interface: (value >> 4) & 0b111,
+            control_interface: value & 0b111,
+        }
+    }
+}
+
+/// Self-diagnostic result as reported by RDDSDR
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SelfDiag {
+    /// Raw register value
+    pub value: u8,
+    /// Register loading detection (D7), factory defaults were loaded from NV memory
+    pub register_loading: bool,
+    /// Functionality detection (D6), the booster and internal state machine check passed
+    pub functionality: bool,
+}
+
+impl SelfDiag {
+    /// Parse the RDDSDR byte
+    pub fn from_value(value: u8) -> Self {
+        SelfDiag {
+            value,
+            register_loading: value & 0b1000_0000 != 0,
+            functionality: value & 0b0100_0000 != 0,
+        }
+    }
+}
+
+/// Convert one pixel as read back by RAMRD (18 bit, one color per byte in D7..D2) to RGB565
+pub fn rgb666_to_rgb565(bytes: [u8; 3]) -> u16 {
+    let [r, g, b] = bytes;
+    ((r as u16 >> 3) << 11) | ((g as u16 >> 2) << 5) | (b as u16 >> 3)
+}
+
+impl<SPI, CS, DC, RST, PinError, SpiError> ST7789V<SPI, CS, DC, RST, PinError, SpiError>
+where
+    SPI: spi::Write<u8, Error = SpiError> + spi::Transfer<u8, Error = SpiError>,
+    CS: OutputPin<Error = PinError>,
+    DC: OutputPin<Error = PinError>,
+    RST: OutputPin<Error = PinError>,
+{
+    /// Read the display power mode (RDDPM).
+    pub fn read_power_mode(&mut self) -> Result<PowerMode, Error<PinError, SpiError>> {
+        let mut buf = [0u8; 1];
+        self.read(Command::RDDPM, &mut buf)?;
+
+        Ok(PowerMode::from_value(buf[0]))
+    }
+
+    /// Read the memory data access control the panel is using (RDDMADCTL).
+    pub fn read_madctl(&mut self) -> Result<Madctl, Error<PinError, SpiError>> {
+        let mut buf = [0u8; 1];
+        self.read(Command::RDD_MADCTL, &mut buf)?;
+
+        Ok(Madctl::from_value(buf[0]))
+    }
+
+    /// Read MADCTL back (RDDMADCTL) and tell whether the panel uses the MY/MX bits the
+    /// current rotation needs, for a self-check during bring-up.
+    ///
+    /// The expected bits are `madctl_for` of the `set_rotate` rotation, mirrored by
+    /// `set_flip`. The other MADCTL bits (MV, ML, RGB, MH) have nothing to do with the
+    /// rotation and are ignored, so a panel left in BGR still passes.
+    pub fn verify_rotation(&mut self) -> Result<bool, Error<PinError, SpiError>> {
+        let expected = madctl_for(self.rotate) ^ self.flip.value();
+        let mask = Flip::Both.value();
+
+        Ok(self.read_madctl()?.value & mask == expected & mask)
+    }
+
+    /// Read the interface pixel format the panel is using (RDDCOLMOD).
+    pub fn read_pixel_format(&mut self) -> Result<PixelFormat, Error<PinError, SpiError>> {
+        let mut buf = [0u8; 1];
+        self.read(Command::RDD_COLMOD, &mut buf)?;
+
+        Ok(PixelFormat::from_value(buf[0]))
+    }
+
+    /// Read the self-diagnostic result (RDDSDR).
+    ///
+    /// The panel runs its checks on SLPOUT and sets both bits when they pass, so a healthy
+    /// panel reads both as set after `init`. Reading back garbage (or both clear) on a
+    /// device that used to pass points at the panel or its interface, which makes this a
+    /// cheap periodic health check.
+    pub fn read_self_diagnostic(&mut self) -> Result<SelfDiag, Error<PinError, SpiError>> {
+        let mut buf = [0u8; 1];
+        self.read(Command::RDDSDR, &mut buf)?;
+
+        Ok(SelfDiag::from_value(buf[0]))
+    }
+
+    /// Read the CABC minimum brightness (RDCABCMB), the value `cabc_min_brightness` wrote
+    /// to WRCABCMB.
+    pub fn read_cabc_min_brightness(&mut self) -> Result<u8, Error<PinError, SpiError>> {
+        let mut buf = [0u8; 1];
+        self.read(Command::RDCABCMB, &mut buf)?;
+
+        Ok(buf[0])
+    }
+
+    /// Read the three ID bytes (RDID1, RDID2, RDID3), what `nvm_write` stamps into the
+    /// panel.
+    pub fn read_id_bytes(&mut self) -> Result<[u8; 3], Error<PinError, SpiError>> {
+        let mut ids = [0u8; 3];
+        for (id, cmd) in ids.iter_mut().zip([Command::RDID1, Command::RDID2, Command::RDID3]) {
+            self.read(cmd, core::slice::from_mut(id))?;
+        }
+
+        Ok(ids)
+    }
+
+    /// Check the wiring before `init`: reads the ID bytes and fails with `NoResponse` if
+    /// they are all 0x00 or all 0xFF, what a floating or stuck MISO line reads.
+    ///
+    /// RDID works straight after power up or reset, so a failure here means wiring (SDO,
+    /// CS, DC, clock) rather than init.
+    pub fn ping_read(&mut self) -> Result<[u8; 3], Error<PinError, SpiError>> {
+        let ids = self.read_id_bytes()?;
+        if ids == [0x00; 3] || ids == [0xFF; 3] {
+            return Err(Error::NoResponse);
+        }
+
+        Ok(ids)
+    }
+
+    /// Runtime self test of the bus: `ping_read`, then MADCTL and COLMOD are written with
+    /// the values the driver keeps and read back through RDDMADCTL and RDDCOLMOD.
+    ///
+    /// A bus in the wrong SPI mode (the panel needs mode 0) shifts every byte by a bit,
+    /// which shows up here as `ReadbackMismatch` while writes alone never fail. Both
+    /// registers keep their values, so the check can run at any time after `init`.
+    pub fn check_communication(&mut self) -> Result<(), Error<PinError, SpiError>> {
+        self.ping_read()?;
+
+        let (madctl, colmod) = (self.madctl, self.colmod);
+        self.command(Command::MADCTL, Some(&[madctl]))?;
+        self.command(Command::COLMOD, Some(&[colmod]))?;
+        if self.read_madctl()?.value != madctl || self.read_pixel_format()?.value != colmod {
+            return Err(Error::ReadbackMismatch);
+        }
+
+        Ok(())
+    }
+
+    /// Read frame memory (RAMRD) from the window `xs`..`xe`, `ys`..`ye` (ends exclusive)
+    /// as raw bytes.
+    ///
+    /// The panel answers RAMRD with one dummy byte which is dropped here, followed by
+    /// three bytes per pixel (R, G, B in D7..D2) regardless of the COLMOD setting, so
+    /// `buf[3 * n..3 * n + 3]` is the n-th pixel of the window.
+    pub fn read_memory_raw(
+        &mut self,
+        xs: u16,
+        ys: u16,
+        xe: u16,
+        ye: u16,
+        buf: &mut [u8],
+    ) -> Result<(), Error<PinError, SpiError>> {
+        self.set_window(xs, ys, xe, ye)?;
+        self.begin_read(Command::RAMRD)?;
+        let mut dummy = [0u8; 1];
+        spi::Transfer::transfer(&mut self.spi, &mut dummy).map_err(Error::Spi)?;
+        buf.fill(0);
+        spi::Transfer::transfer(&mut self.spi, buf).map_err(Error::Spi)?;
+        self.end_read()
+    }
+
+    /// Read frame memory (RAMRD) from the window `xs`..`xe`, `ys`..`ye` (ends exclusive)
+    /// converted back to RGB565, one pixel per entry of `out`.
+    ///
+    /// See `read_memory_raw` for the byte layout on the bus. The two least significant
+    /// bits of red and blue are lost in the conversion, which matches what was written
+    /// in 16 bit mode.
+    pub fn read_pixels(
+        &mut self,
+        xs: u16,
+        ys: u16,
+        xe: u16,
+        ye: u16,
+        out: &mut [u16],
+    ) -> Result<(), Error<PinError, SpiError>> {
+        self.set_window(xs, ys, xe, ye)?;
+        self.begin_read(Command::RAMRD)?;
+        let mut dummy = [0u8; 1];
+        spi::Transfer::transfer(&mut self.spi, &mut dummy).map_err(Error::Spi)?;
+
+        let mut buf = [0u8; 96];
+        for pixels in out.chunks_mut(buf.len() / 3) {
+            let bytes = &mut buf[..pixels.len() * 3];
+            bytes.fill(0);
+            spi::Transfer::transfer(&mut self.spi, bytes).map_err(Error::Spi)?;
+            for (pixel, rgb) in pixels.iter_mut().zip(bytes.chunks(3)) {
+                *pixel = rgb666_to_rgb565([rgb[0], rgb[1], rgb[2]]);
+            }
+        }
+        self.end_read()
+    }
+
+    /// Send a command and clock `buf.len()` bytes back with CS held low.
+    fn read(&mut self, cmd: Command, buf: &mut [u8]) -> Result<(), Error<PinError, SpiError>> {
+        self.begin_read(cmd)?;
+        buf.fill(0);
+        spi::Transfer::transfer(&mut self.spi, buf).map_err(Error::Spi)?;
+        self.end_read()
+    }
+
+    /// Assert CS, send the command and switch DC to data for the answer.
+    fn begin_read(&mut self, cmd: Command) -> Result<(), Error<PinError, SpiError>> {
+        self.select()?;
+        self.cfg.dc.set_low().map_err(Error::Pin)?;
+        spi::Write::write(&mut self.spi, &[cmd.value()]).map_err(Error::Spi)?;
+        self.cfg.dc.set_high().map_err(Error::Pin)?;
+
+        Ok(())
+    }
+
+    fn end_read(&mut self) -> Result<(), Error<PinError, SpiError>> {
+        self.deselect()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{rgb666_to_rgb565, Madctl, PixelFormat, PowerMode, SelfDiag};
+    use crate::command::Command;
+    use crate::mock::{display, Event};
+    use crate::Error;
+
+    #[test]
+    fn communication_check() {
+        let (madctl, colmod) = (crate::DEFAULT_MADCTL, crate::DEFAULT_COLMOD);
+        let (mut display, _, log) = display(240, 320);
+        log.respond(&[0x85, 0x85, 0x52, madctl, colmod]);
+        display.check_communication().unwrap();
+
+        // a bus in mode 3 answers with every byte shifted by one bit
+        log.respond(&[0x85, 0x85, 0x52, madctl >> 1, colmod >> 1]);
+        assert!(matches!(display.check_communication(), Err(Error::ReadbackMismatch)));
+
+        log.respond(&[0xFF, 0xFF, 0xFF]);
+        assert!(matches!(display.check_communication(), Err(Error::NoResponse)));
+    }
+
+    #[test]
+    fn rotation_readback() {
+        let (mut display, _, log) = display(240, 320);
+        display.set_rotate(crate::Rotate::Rotate90).unwrap();
+
+        log.clear();
+        log.respond(&[crate::madctl_for(crate::Rotate::Rotate90) | 0b11]);
+        assert!(display.verify_rotation().unwrap());
+        // BGR and the rest are not part of the rotation
+        log.respond(&[crate::madctl_for(crate::Rotate::Rotate90) | 0x08]);
+        assert!(display.verify_rotation().unwrap());
+        log.respond(&[crate::madctl_for(crate::Rotate::Rotate180)]);
+        assert!(!display.verify_rotation().unwrap());
+
+        display.set_flip(crate::Flip::Vertical).unwrap();
+        log.respond(&[crate::madctl_for(crate::Rotate::Rotate90) ^ crate::Flip::Vertical.value()]);
+        assert!(display.verify_rotation().unwrap());
+        assert_eq!(log.commands()[0].0, Command::RDD_MADCTL.value());
+    }
+
+    #[test]
+    fn power_mode_read() {
+        let (mut display, _, log) = display(240, 320);
+        log.respond(&[0b1001_1100]);
+
+        let mode = display.read_power_mode().unwrap();
+        assert_eq!(
+            mode,
+            PowerMode {
+                booster_on: true,
+                idle: false,
+                partial: false,
+                sleep_out: true,
+                normal: true,
+                display_on: true,
+            }
+        );
+        assert_eq!(
+            log.events(),
+            [
+                Event::Cs(false),
+                Event::Dc(false),
+                Event::Write(vec![Command::RDDPM.value()]),
+                Event::Dc(true),
+                Event::Read(vec![0b1001_1100]),
+                Event::Cs(true),
+            ]
+        );
+    }
+
+    #[test]
+    fn cabc_min_brightness_read() {
+        let (mut display, _, log) = display(240, 320);
+        display.cabc_min_brightness(0x40).unwrap();
+        log.clear();
+        log.respond(&[0x40]);
+
+        assert_eq!(display.read_cabc_min_brightness().unwrap(), 0x40);
+        assert_eq!(log.bytes()[0], Command::RDCABCMB.value());
+        assert_eq!(log.bytes()[0], 0x5F);
+    }
+
+    #[test]
+    fn madctl_and_pixel_format() {
+        let (mut display, _, log) = display(240, 320);
+        log.respond(&[0b1010_1000, 0x55]);
+
+        let madctl = display.read_madctl().unwrap();
+        assert_eq!(madctl, Madctl::from_value(0b1010_1000));
+        assert!(madctl.page_bottom_to_top && madctl.page_column_reverse && madctl.bgr);
+        assert!(!madctl.column_left_to_right);
+
+        let format = display.read_pixel_format().unwrap();
+        assert_eq!(format, PixelFormat { value: 0x55, rgb_interface: 0b101, control_interface: 0b101 });
+    }
+
+    #[test]
+    fn self_diagnostic() {
+        let (mut display, _, log) = display(240, 320);
+        log.respond(&[0b1100_0000, 0b0100_0000]);
+
+        let diag = display.read_self_diagnostic().unwrap();
+        assert!(diag.register_loading && diag.functionality);
+        assert_eq!(
+            display.read_self_diagnostic().unwrap(),
+            SelfDiag { value: 0x40, register_loading: false, functionality: true }
+        );
+        assert_eq!(log.bytes(), [Command::RDDSDR.value(); 2]);
+    }
+
+    #[test]
+    fn id_bytes_read() {
+        let (mut display, _, log) = display(240, 320);
+        log.respond(&[0x85, 0x85, 0x52]);
+
+        assert_eq!(display.read_id_bytes().unwrap(), [0x85, 0x85, 0x52]);
+        let cmds: Vec<u8> = log.commands().into_iter().map(|c| c.0).collect();
+        assert_eq!(cmds, [Command::RDID1.value(), Command::RDID2.value(), Command::RDID3.value()]);
+    }
+
+    #[test]
+    fn ping_read_floating_bus() {
+        let (mut display, _, log) = display(240, 320);
+        // nothing queued, reads return 0xFF like a floating MISO
+        assert!(matches!(display.ping_read(), Err(Error::NoResponse)));
+
+        log.respond(&[0x85, 0x85, 0x52]);
+        assert_eq!(display.ping_read().unwrap(), [0x85, 0x85, 0x52]);
+    }
+
+    #[test]
+    fn ram_read_skips_dummy_and_converts() {
+        let (mut display, _, log) = display(240, 320);
+        // byte 0 is the dummy, then R, G, B for each pixel
+        log.respond(&[0xAA, 0xF8, 0x00, 0x00, 0x00, 0xFC, 0x00, 0x84, 0x10, 0x84]);
+
+        let mut raw = [0u8; 9];
+        display.read_memory_raw(0, 0, 3, 1, &mut raw).unwrap();
+        assert_eq!(raw, [0xF8, 0x00, 0x00, 0x00, 0xFC, 0x00, 0x84, 0x10, 0x84]);
+
+        log.respond(&[0xAA, 0xF8, 0x00, 0x00, 0x00, 0xFC, 0x00, 0x84, 0x10, 0x84]);
+        let mut pixels = [0u16; 3];
+        display.read_pixels(0, 0, 3, 1, &mut pixels).unwrap();
+        assert_eq!(pixels, [0xF800, 0x07E0, 0x8090]);
+        assert_eq!(rgb666_to_rgb565([0xFC, 0xFC, 0xFC]), 0xFFFF);
+    }
+}
@@ -0,0 +1,242 @@
+//! RGB (DPI) interface configuration, for boards that stream pixels over the parallel
+//! RGB bus and only use SPI to configure the controller.
+
+use embedded_hal::blocking::delay::DelayMs;
+use embedded_hal::blocking::spi;
+use embedded_hal::digital::v2::OutputPin;
+
+use crate::command::Command;
+use crate::{Error, ST7789V};
+
+/// Display operation selection (RAMCTRL DM)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DisplayOperation {
+    /// MCU interface
+    Mcu = 0b00,
+    /// RGB interface
+    Rgb = 0b01,
+    /// VSYNC interface
+    Vsync = 0b10,
+}
+
+/// RGB interface synchronization mode (RGBCTRL RCM)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RgbSyncMode {
+    /// Data enable mode, uses ENABLE together with VSYNC/HSYNC
+    DataEnable = 0b10,
+    /// HV mode, only VSYNC/HSYNC and the porches
+    Hv = 0b11,
+}
+
+/// RGB interface config, written by `ST7789V::set_rgb_interface`
+pub struct RgbInterfaceConfig {
+    ram_access_rgb: bool,
+    operation: DisplayOperation,
+    direct: bool,
+    sync_mode: RgbSyncMode,
+    vsync_active_high: bool,
+    hsync_active_high: bool,
+    dotclk_falling: bool,
+    enable_active_low: bool,
+    vertical_back_porch: u8,
+    horizontal_back_porch: u8,
+}
+
+impl Default for RgbInterfaceConfig {
+    /// RGB interface for display and RAM access, HV mode, active low syncs, data latched
+    /// on the rising DOTCLK edge and the reset default porches.
+    fn default() -> Self {
+        RgbInterfaceConfig {
+            ram_access_rgb: true,
+            operation: DisplayOperation::Rgb,
+            direct: false,
+            sync_mode: RgbSyncMode::Hv,
+            vsync_active_high: false,
+            hsync_active_high: false,
+            dotclk_falling: false,
+            enable_active_low: false,
+            vertical_back_porch: 0x02,
+            horizontal_back_porch: 0x14,
+        }
+    }
+}
+
+impl RgbInterfaceConfig {
+    /// RAM access from the RGB interface instead of the MCU interface (RM)
+    pub fn ram_access_rgb(&mut self, rgb: bool) -> &mut Self {
+        self.ram_access_rgb = rgb;
+        self
+    }
+
+    /// Which interface drives the display operation (DM)
+    pub fn operation(&mut self, operation: DisplayOperation) -> &mut Self {
+        self.operation = operation;
+        self
+    }
+
+    /// Write RGB data straight to the shift register, bypassing frame memory (WO)
+    pub fn direct(&mut self, direct: bool) -> &mut Self {
+        self.direct = direct;
+        self
+    }
+
+    /// Synchronization mode (RCM)
+    pub fn sync_mode(&mut self, sync_mode: RgbSyncMode) -> &mut Self {
+        self.sync_mode = sync_mode;
+        self
+    }
+
+    /// VSYNC polarity (VSPL), active low by default
+    pub fn vsync_active_high(&mut self, high: bool) -> &mut Self {
+        self.vsync_active_high = high;
+        self
+    }
+
+    /// HSYNC polarity (HSPL), active low by default
+    pub fn hsync_active_high(&mut self, high: bool) -> &mut Self {
+        self.hsync_active_high = high;
+        self
+    }
+
+    /// Latch data on the falling DOTCLK edge (DPL), rising by default
+    pub fn dotclk_falling(&mut self, falling: bool) -> &mut Self {
+        self.dotclk_falling = falling;
+        self
+    }
+
+    /// ENABLE polarity (EPL), active high by default
+    pub fn enable_active_low(&mut self, low: bool) -> &mut Self {
+        self.enable_active_low = low;
+        self
+    }
+
+    /// Vertical back porch in lines (VBP, 0x02 - 0x7F), HV mode only
+    pub fn vertical_back_porch(&mut self, lines: u8) -> &mut Self {
+        self.vertical_back_porch = lines & 0x7F;
+        self
+    }
+
+    /// Horizontal back porch in DOTCLK cycles (HBP, 0x02 - 0x1F), HV mode only
+    pub fn horizontal_back_porch(&mut self, clocks: u8) -> &mut Self {
+        self.horizontal_back_porch = clocks & 0x1F;
+        self
+    }
+
+    /// RAMCTRL parameters
+    pub fn ram_control(&self) -> [u8; 2] {
+        let rm = if self.ram_access_rgb { 0b0001_0000 } else { 0 };
+        // second parameter left at its reset default (big endian, MSB first, 0xF0)
+        [rm | self.operation as u8, 0xF0]
+    }
+
+    /// RGBCTRL parameters
+    pub fn rgb_control(&self) -> [u8; 3] {
+        let mut value = (self.sync_mode as u8) << 5;
+        if self.direct {
+            value |= 0b1000_0000;
+        }
+        if self.vsync_active_high {
+            value |= 0b0000_1000;
+        }
+        if self.hsync_active_high {
+            value |= 0b0000_0100;
+        }
+        if self.dotclk_falling {
+            value |= 0b0000_0010;
+        }
+        if self.enable_active_low {
+            value |= 0b0000_0001;
+        }
+        [value, self.vertical_back_porch, self.horizontal_back_porch]
+    }
+}
+
+impl<SPI, CS, DC, RST, PinError, SpiError> ST7789V<SPI, CS, DC, RST, PinError, SpiError>
+where
+    SPI: spi::Write<u8, Error = SpiError>,
+    CS: OutputPin<Error = PinError>,
+    DC: OutputPin<Error = PinError>,
+    RST: OutputPin<Error = PinError>,
+{
+    /// Configure the RGB interface: RAM control (RAMCTRL) and RGB interface control
+    /// (RGBCTRL).
+    pub fn set_rgb_interface(
+        &mut self,
+        config: &RgbInterfaceConfig,
+    ) -> Result<&mut Self, Error<PinError, SpiError>> {
+        self.command(Command::RAMCTRL, Some(&config.ram_control()))?
+            .command(Command::RGBCTRL, Some(&config.rgb_control()))?;
+
+        Ok(self)
+    }
+
+    /// Minimal init for panels fed over the RGB interface: reset, sleep out, COLMOD,
+    /// `set_rgb_interface` with `config`, display on.
+    ///
+    /// Gamma, power, porch and the other frame memory oriented registers of `init` stay
+    /// at their reset defaults. COLMOD is still written, its upper bits select the RGB
+    /// interface pixel format, see `color_mode`.
+    pub fn init_rgb_interface<DELAY>(
+        &mut self,
+        config: &RgbInterfaceConfig,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<PinError, SpiError>>
+    where
+        DELAY: DelayMs<u16>,
+    {
+        self.hard_reset(delay)?.sleep_out(delay)?;
+        let colmod = self.colmod;
+        self.command(Command::COLMOD, Some(&[colmod]))?
+            .set_rgb_interface(config)?
+            .display_on()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DisplayOperation, RgbInterfaceConfig, RgbSyncMode};
+    use crate::command::Command;
+    use crate::mock::display;
+
+    #[test]
+    fn rgb_interface_init() {
+        let (mut display, mut delay, log) = display(240, 320);
+        display
+            .init_rgb_interface(&RgbInterfaceConfig::default(), &mut delay)
+            .unwrap();
+
+        let cmds: Vec<u8> = log.commands().into_iter().map(|c| c.0).collect();
+        assert_eq!(
+            cmds,
+            [
+                Command::SLPOUT.value(),
+                Command::COLMOD.value(),
+                Command::RAMCTRL.value(),
+                Command::RGBCTRL.value(),
+                Command::DISPON.value(),
+            ]
+        );
+    }
+
+    #[test]
+    fn rgb_interface_params() {
+        let (mut display, _, log) = display(240, 320);
+        let mut config = RgbInterfaceConfig::default();
+        config
+            .operation(DisplayOperation::Rgb)
+            .sync_mode(RgbSyncMode::DataEnable)
+            .vsync_active_high(true)
+            .enable_active_low(true);
+        display.set_rgb_interface(&config).unwrap();
+
+        assert_eq!(
+            log.commands(),
+            [
+                (Command::RAMCTRL.value(), vec![0x11, 0xF0]),
+                (Command::RGBCTRL.value(), vec![0b0100_1001, 0x02, 0x14]),
+            ]
+        );
+    }
+}
@@ -0,0 +1,221 @@
+//! Partial area + vertical scrolling, for tickers that scroll one band of the screen.
+
+use embedded_hal::blocking::spi;
+use embedded_hal::digital::v2::OutputPin;
+
+use crate::command::Command;
+use crate::{DisplayMode, Error, ST7789V};
+
+/// Number of lines in the controller frame memory
+pub const GRAM_LINES: u16 = 320;
+
+impl<SPI, CS, DC, RST, PinError, SpiError> ST7789V<SPI, CS, DC, RST, PinError, SpiError>
+where
+    SPI: spi::Write<u8, Error = SpiError>,
+    CS: OutputPin<Error = PinError>,
+    DC: OutputPin<Error = PinError>,
+    RST: OutputPin<Error = PinError>,
+{
+    /// Partial area (PTLAR), frame memory lines `start` to `end` inclusive.
+    ///
+    /// Only takes effect in partial mode, see `partial_display_mode`.
+    pub fn partial_area(
+        &mut self,
+        start: u16,
+        end: u16,
+    ) -> Result<&mut Self, Error<PinError, SpiError>> {
+        if start > end || end >= GRAM_LINES {
+            return Err(Error::InvalidRowAddress);
+        }
+        let [sh, sl] = start.to_be_bytes();
+        let [eh, el] = end.to_be_bytes();
+        self.command(Command::PTLAR, Some(&[sh, sl, eh, el]))?;
+
+        Ok(self)
+    }
+
+    /// Vertical scrolling definition (VSCRDEF).
+    ///
+    /// `top_fixed` lines stay at the top, `scroll_height` lines scroll and the remaining
+    /// lines of the 320 line frame memory stay at the bottom.
+    pub fn vertical_scroll_definition(
+        &mut self,
+        top_fixed: u16,
+        scroll_height: u16,
+    ) -> Result<&mut Self, Error<PinError, SpiError>> {
+        if scroll_height == 0 || top_fixed as u32 + scroll_height as u32 > GRAM_LINES as u32 {
+            return Err(Error::InvalidRowAddress);
+        }
+        let bottom_fixed = GRAM_LINES - top_fixed - scroll_height;
+        let [th, tl] = top_fixed.to_be_bytes();
+        let [vh, vl] = scroll_height.to_be_bytes();
+        let [bh, bl] = bottom_fixed.to_be_bytes();
+        self.command(Command::VSCRDEF, Some(&[th, tl, vh, vl, bh, bl]))?;
+
+        Ok(self)
+    }
+
+    /// Vertical scroll start address (VSCRSADD), the frame memory line shown at the top
+    /// of the scroll area.
+    pub fn vertical_scroll_start(
+        &mut self,
+        line: u16,
+    ) -> Result<&mut Self, Error<PinError, SpiError>> {
+        self.command(Command::VSCRSADD, Some(&line.to_be_bytes()))?;
+
+        Ok(self)
+    }
+
+    /// Set up lines `top` to `top + height` as both the partial area and the vertical
+    /// scroll area and enter partial mode.
+    ///
+    /// The partial area and the scroll area must cover exactly the same lines, otherwise
+    /// lines scroll into (or out of) the part of the panel that is not refreshed and the
+    /// band tears at its edges. Lines outside the partial area are not driven from frame
+    /// memory while the region is active. The lines are frame memory lines and do not
+    /// follow `set_rotate`.
+    #[allow(clippy::type_complexity)]
+    pub fn scroll_region(
+        &mut self,
+        top: u16,
+        height: u16,
+    ) -> Result<ScrollRegion<'_, SPI, CS, DC, RST, PinError, SpiError>, Error<PinError, SpiError>> {
+        if height == 0 {
+            return Err(Error::InvalidRowAddress);
+        }
+        self.vertical_scroll_definition(top, height)?
+            .partial_area(top, top + height - 1)?
+            .vertical_scroll_start(top)?
+            .partial_display_mode()?;
+
+        Ok(ScrollRegion {
+            display: self,
+            top,
+            height,
+            offset: 0,
+        })
+    }
+}
+
+/// A band of the panel that scrolls while the rest stays put, see `ST7789V::scroll_region`
+pub struct ScrollRegion<'a, SPI, CS, DC, RST, PinError, SpiError>
+where
+    SPI: spi::Write<u8, Error = SpiError>,
+    CS: OutputPin<Error = PinError>,
+    DC: OutputPin<Error = PinError>,
+    RST: OutputPin<Error = PinError>,
+{
+    display: &'a mut ST7789V<SPI, CS, DC, RST, PinError, SpiError>,
+    top: u16,
+    height: u16,
+    offset: u16,
+}
+
+impl<'a, SPI, CS, DC, RST, PinError, SpiError>
+    ScrollRegion<'a, SPI, CS, DC, RST, PinError, SpiError>
+where
+    SPI: spi::Write<u8, Error = SpiError>,
+    CS: OutputPin<Error = PinError>,
+    DC: OutputPin<Error = PinError>,
+    RST: OutputPin<Error = PinError>,
+{
+    /// Scroll the band up by `lines`, wrapping around at its end.
+    pub fn advance(&mut self, lines: u16) -> Result<(), Error<PinError, SpiError>> {
+        self.set_scroll_start(self.offset as u32 + lines as u32)
+    }
+
+    /// Show the band starting `offset` lines into it, taken modulo the band height.
+    ///
+    /// The start line sent is always within `top..top + height`, so an animation can
+    /// count `offset` up forever and the band loops without jumping. Wrapping at the
+    /// 320 lines of frame memory instead would show lines from outside the band.
+    pub fn set_scroll_start(&mut self, offset: u32) -> Result<(), Error<PinError, SpiError>> {
+        debug_assert_eq!(
+            self.display.current_mode(),
+            DisplayMode::Partial,
+            "scroll region used after leaving partial mode"
+        );
+        self.offset = (offset % self.height as u32) as u16;
+        self.display.vertical_scroll_start(self.top + self.offset)?;
+
+        Ok(())
+    }
+
+    /// Current scroll offset within the band
+    pub fn offset(&self) -> u16 {
+        self.offset
+    }
+
+    /// The display, for drawing into the band (or elsewhere) while scrolling.
+    pub fn display(&mut self) -> &mut ST7789V<SPI, CS, DC, RST, PinError, SpiError> {
+        self.display
+    }
+
+    /// Reset the scroll position and return to normal mode.
+    pub fn finish(self) -> Result<(), Error<PinError, SpiError>> {
+        self.display.vertical_scroll_start(self.top)?.normal_mode()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::command::Command;
+    use crate::mock::display;
+
+    #[test]
+    fn region_setup_and_wrap() {
+        let (mut display, _, log) = display(240, 320);
+        let mut region = display.scroll_region(100, 40).unwrap();
+
+        assert_eq!(
+            log.commands(),
+            [
+                (Command::VSCRDEF.value(), vec![0, 100, 0, 40, 0, 180]),
+                (Command::PTLAR.value(), vec![0, 100, 0, 139]),
+                (Command::VSCRSADD.value(), vec![0, 100]),
+                (Command::PTLON.value(), vec![]),
+            ]
+        );
+
+        log.clear();
+        region.advance(30).unwrap();
+        region.advance(15).unwrap();
+        assert_eq!(region.offset(), 5);
+        assert_eq!(
+            log.commands(),
+            [
+                (Command::VSCRSADD.value(), vec![0, 130]),
+                (Command::VSCRSADD.value(), vec![0, 105]),
+            ]
+        );
+    }
+
+    #[test]
+    fn scroll_start_stays_in_band() {
+        let (mut display, _, log) = display(240, 320);
+        let mut region = display.scroll_region(300, 20).unwrap();
+        log.clear();
+
+        region.set_scroll_start(25).unwrap();
+        region.advance(u16::MAX).unwrap();
+        region.set_scroll_start(u32::MAX).unwrap();
+        assert_eq!(
+            log.commands(),
+            [
+                (Command::VSCRSADD.value(), vec![0x01, 0x31]),
+                (Command::VSCRSADD.value(), vec![0x01, 0x2C]),
+                (Command::VSCRSADD.value(), vec![0x01, 0x3B]),
+            ]
+        );
+    }
+
+    #[test]
+    fn region_must_fit_frame_memory() {
+        let (mut display, _, _) = display(240, 320);
+
+        assert!(display.scroll_region(300, 40).is_err());
+        assert!(display.scroll_region(0, 0).is_err());
+    }
+}
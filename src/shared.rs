@@ -0,0 +1,142 @@
+//! Sharing one SPI bus between several displays (or other devices), each with its own CS.
+//!
+//! Every driver instance takes a `SharedSpi` pointing at the same `RefCell` and its own
+//! chip select pin. The driver raises CS after every command and data burst, so only the
+//! display being talked to listens. Displays sharing a bus must be built with `with_cs`
+//! (or a config with CS), without CS a panel would take every byte on the bus.
+//!
+//! ```ignore
+//! let bus = RefCell::new(spi);
+//! let mut left = ST7789V::with_cs(SharedSpi::new(&bus), cs_left, dc, rst, 240, 320)?;
+//! let mut right = ST7789V::with_cs(SharedSpi::new(&bus), cs_right, dc2, rst2, 240, 320)?;
+//! ```
+//!
+//! Each write borrows the bus on its own. `ST7789V::locked_transaction` keeps the bus
+//! borrowed from the first command to the last data byte, for sequences that must not
+//! have another device's traffic in between.
+
+use core::cell::RefCell;
+
+use embedded_hal::blocking::spi;
+use embedded_hal::digital::v2::OutputPin;
+
+use crate::{Error, Transaction, ST7789V};
+
+/// SPI bus handle borrowing a bus shared through a `RefCell`
+pub struct SharedSpi<'a, SPI>(&'a RefCell<SPI>);
+
+impl<'a, SPI> SharedSpi<'a, SPI> {
+    /// Handle to the shared `bus`
+    pub fn new(bus: &'a RefCell<SPI>) -> Self {
+        SharedSpi(bus)
+    }
+}
+
+impl<'a, SPI> spi::Write<u8> for SharedSpi<'a, SPI>
+where
+    SPI: spi::Write<u8>,
+{
+    type Error = SPI::Error;
+
+    fn write(&mut self, words: &[u8]) -> Result<(), Self::Error> {
+        self.0.borrow_mut().write(words)
+    }
+}
+
+impl<'a, SPI> spi::Transfer<u8> for SharedSpi<'a, SPI>
+where
+    SPI: spi::Transfer<u8>,
+{
+    type Error = SPI::Error;
+
+    fn transfer<'w>(&mut self, words: &'w mut [u8]) -> Result<&'w [u8], Self::Error> {
+        self.0.borrow_mut().transfer(words)
+    }
+}
+
+impl<'a, BUS, CS, DC, RST, PinError, SpiError>
+    ST7789V<SharedSpi<'a, BUS>, CS, DC, RST, PinError, SpiError>
+where
+    BUS: spi::Write<u8, Error = SpiError>,
+    CS: OutputPin<Error = PinError>,
+    DC: OutputPin<Error = PinError>,
+    RST: OutputPin<Error = PinError>,
+{
+    /// `transaction` with the shared bus borrowed for the whole of `f`, so no other
+    /// `SharedSpi` user gets between its commands and data.
+    ///
+    /// Another user of the bus reaching for it meanwhile (from an interrupt, say) panics on
+    /// the `RefCell` borrow instead of corrupting the write.
+    #[allow(clippy::type_complexity)]
+    pub fn locked_transaction<F, R>(&mut self, f: F) -> Result<R, Error<PinError, SpiError>>
+    where
+        F: FnOnce(
+            &mut Transaction<'_, BUS, DC, PinError, SpiError>,
+        ) -> Result<R, Error<PinError, SpiError>>,
+    {
+        let mut bus = self.spi.0.borrow_mut();
+        self.select()?;
+        let result = f(&mut Transaction::new(&mut *bus, &mut self.cfg.dc));
+        drop(bus);
+        self.deselect()?;
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::RefCell;
+
+    use super::SharedSpi;
+    use crate::mock::{Event, Log, MockPin, MockSpi, PinKind};
+    use crate::ST7789V;
+
+    #[test]
+    fn two_displays_one_bus() {
+        let log = Log::default();
+        let cs_log = (Log::default(), Log::default());
+        let bus = RefCell::new(MockSpi(log.clone()));
+        let pins = |cs: &Log| {
+            (
+                MockPin(cs.clone(), PinKind::Cs),
+                MockPin(log.clone(), PinKind::Dc),
+                MockPin(log.clone(), PinKind::Rst),
+            )
+        };
+
+        let (cs, dc, rst) = pins(&cs_log.0);
+        let mut first = ST7789V::with_cs(SharedSpi::new(&bus), cs, dc, rst, 240, 320).unwrap();
+        let (cs, dc, rst) = pins(&cs_log.1);
+        let mut second = ST7789V::with_cs(SharedSpi::new(&bus), cs, dc, rst, 240, 320).unwrap();
+
+        first.display_on().unwrap();
+        second.display_off().unwrap();
+
+        assert_eq!(log.bytes(), [0x29, 0x28]);
+        // each display only selects itself and lets go of the bus afterwards
+        for cs in [&cs_log.0, &cs_log.1] {
+            assert_eq!(cs.events(), [Event::Cs(true), Event::Cs(false), Event::Cs(true)]);
+        }
+    }
+
+    #[test]
+    fn locked_transaction_holds_bus() {
+        let log = Log::default();
+        let bus = RefCell::new(MockSpi(log.clone()));
+        let cs = MockPin(log.clone(), PinKind::Cs);
+        let dc = MockPin(log.clone(), PinKind::Dc);
+        let rst = MockPin(log.clone(), PinKind::Rst);
+        let mut display = ST7789V::with_cs(SharedSpi::new(&bus), cs, dc, rst, 240, 320).unwrap();
+
+        display
+            .locked_transaction(|t| {
+                assert!(bus.try_borrow_mut().is_err());
+                t.cmd(0x2A)?.data(&[0, 0, 0, 9])?;
+                Ok(())
+            })
+            .unwrap();
+        assert!(bus.try_borrow_mut().is_ok());
+        assert_eq!(log.bytes(), [0x2A, 0, 0, 0, 9]);
+    }
+}
@@ -0,0 +1,176 @@
+//! Tearing effect (TE) output, for syncing frame writes to the panel refresh.
+
+use embedded_hal::blocking::delay::DelayUs;
+use embedded_hal::blocking::spi;
+use embedded_hal::digital::v2::{InputPin, OutputPin};
+
+use crate::command::Command;
+use crate::{Error, ST7789V};
+
+/// How long `draw_synced` waits for a TE edge before giving up, in microseconds. Over two
+/// frames at the slowest refresh FRCTRL2 and PORCTRL allow, about 23 Hz.
+pub const TE_TIMEOUT_US: u32 = 100_000;
+
+/// TE is sampled this often while waiting, in microseconds
+const TE_POLL_US: u32 = 10;
+
+impl<SPI, CS, DC, RST, PinError, SpiError> ST7789V<SPI, CS, DC, RST, PinError, SpiError>
+where
+    SPI: spi::Write<u8, Error = SpiError>,
+    CS: OutputPin<Error = PinError>,
+    DC: OutputPin<Error = PinError>,
+    RST: OutputPin<Error = PinError>,
+{
+    /// Enable the TE output (TEON). With `vblank_only` TE pulses once per frame during
+    /// V-blank, otherwise it also pulses on every H-blank.
+    pub fn tearing_effect_on(
+        &mut self,
+        vblank_only: bool,
+    ) -> Result<&mut Self, Error<PinError, SpiError>> {
+        let mode = if vblank_only { 0x00 } else { 0x01 };
+        self.command(Command::TEON, Some(&[mode]))?;
+
+        Ok(self)
+    }
+
+    /// Disable the TE output (TEOFF), the line stays low.
+    pub fn tearing_effect_off(&mut self) -> Result<&mut Self, Error<PinError, SpiError>> {
+        self.command(Command::TEOFF, None)?;
+
+        Ok(self)
+    }
+
+    /// Make TE fire when the panel reaches scanline `line` (TESCAN) instead of at the start
+    /// of V-blank, for starting a write mid frame right behind the refresh.
+    ///
+    /// `line` counts the 320 display lines plus the front and back porch of `set_porch`,
+    /// it must lie in `0..total_lines`, otherwise `InvalidRowAddress` is returned. Takes
+    /// effect with TE enabled through `tearing_effect_on(true)`.
+    pub fn set_tear_scanline(&mut self, line: u16) -> Result<&mut Self, Error<PinError, SpiError>> {
+        let total_lines = 320 + self.porch[0] as u16 + self.porch[1] as u16;
+        if line >= total_lines {
+            return Err(Error::InvalidRowAddress);
+        }
+        self.command(Command::TESCAN, Some(&line.to_be_bytes()))?;
+
+        Ok(self)
+    }
+
+    /// Wait for the falling edge of the TE line, then call `f` to draw the frame.
+    ///
+    /// TE must be enabled with `tearing_effect_on(true)`. If TE is currently low the
+    /// driver first waits for it to go high, so `f` always starts at the beginning of a
+    /// blanking period rather than somewhere in its middle. Pass `None` on boards
+    /// without the TE line wired, `f` is then called immediately. The TE pin is passed
+    /// in rather than owned, like the backlight in `power_on`.
+    ///
+    /// TE is polled every 10 µs through `delay`. Without an edge within `TE_TIMEOUT_US`
+    /// (TEON never sent, the line stuck or not connected) `NoResponse` is returned and
+    /// `f` is not called.
+    pub fn draw_synced<TE, DELAY, F, R>(
+        &mut self,
+        te: Option<&mut TE>,
+        delay: &mut DELAY,
+        f: F,
+    ) -> Result<R, Error<PinError, SpiError>>
+    where
+        TE: InputPin<Error = PinError>,
+        DELAY: DelayUs<u32>,
+        F: FnOnce(&mut Self) -> Result<R, Error<PinError, SpiError>>,
+    {
+        if let Some(te) = te {
+            let mut waited = 0;
+            for high in [true, false].iter().copied() {
+                while te.is_high().map_err(Error::Pin)? != high {
+                    if waited >= TE_TIMEOUT_US {
+                        return Err(Error::NoResponse);
+                    }
+                    delay.delay_us(TE_POLL_US);
+                    waited += TE_POLL_US;
+                }
+            }
+        }
+
+        f(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::command::Command;
+    use crate::mock::{display, Event, MockTe};
+    use crate::{Error, TE_TIMEOUT_US};
+
+    #[test]
+    fn te_on_off() {
+        let (mut display, _, log) = display(240, 320);
+        display.tearing_effect_on(true).unwrap().tearing_effect_off().unwrap();
+
+        assert_eq!(
+            log.commands(),
+            [(Command::TEON.value(), vec![0x00]), (Command::TEOFF.value(), vec![])]
+        );
+    }
+
+    #[test]
+    fn tear_scanline_big_endian() {
+        let (mut display, _, log) = display(240, 320);
+        display.set_tear_scanline(300).unwrap();
+        assert_eq!(log.commands(), [(Command::TESCAN.value(), vec![0x01, 0x2C])]);
+
+        // 320 lines plus the default porches of 12 each
+        assert!(display.set_tear_scanline(343).is_ok());
+        assert!(matches!(display.set_tear_scanline(344), Err(Error::InvalidRowAddress)));
+    }
+
+    #[test]
+    fn draws_after_falling_edge() {
+        let (mut display, mut delay, log) = display(240, 320);
+        let mut te = MockTe::new(log.clone(), &[false, false, true, true, false]);
+
+        display
+            .draw_synced(Some(&mut te), &mut delay, |d| {
+                d.display_on()?;
+                Ok(())
+            })
+            .unwrap();
+
+        assert_eq!(te.remaining(), 0);
+        let events = log.events();
+        let edge = events.iter().rposition(|e| matches!(e, Event::Te(_))).unwrap();
+        let first_write = events.iter().position(|e| matches!(e, Event::Write(_))).unwrap();
+        assert!(edge < first_write);
+        assert_eq!(log.commands(), [(Command::DISPON.value(), vec![])]);
+    }
+
+    #[test]
+    fn draws_immediately_without_te() {
+        let (mut display, mut delay, log) = display(240, 320);
+
+        let drawn = display
+            .draw_synced(None::<&mut MockTe>, &mut delay, |_| Ok(true))
+            .unwrap();
+        assert!(drawn);
+        assert!(log.events().is_empty());
+    }
+
+    #[test]
+    fn gives_up_on_stuck_te() {
+        let (mut display, mut delay, log) = display(240, 320);
+        // a pin without scripted levels reads low forever
+        let mut te = MockTe::new(log.clone(), &[]);
+
+        let result = display.draw_synced(Some(&mut te), &mut delay, |_| Ok(()));
+        assert!(matches!(result, Err(Error::NoResponse)));
+        let waited: u32 = log
+            .events()
+            .iter()
+            .map(|e| match e {
+                Event::DelayUs(us) => *us,
+                _ => 0,
+            })
+            .sum();
+        assert_eq!(waited, TE_TIMEOUT_US);
+        assert!(log.commands().is_empty());
+    }
+}
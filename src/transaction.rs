@@ -0,0 +1,100 @@
+//! Raw transactions, for init sequences that need commands and data interleaved with CS
+//! held low throughout.
+
+use core::marker::PhantomData;
+
+use embedded_hal::blocking::spi;
+use embedded_hal::digital::v2::OutputPin;
+
+use crate::{Error, ST7789V};
+
+/// Handle passed to the closure of `ST7789V::transaction`, CS stays asserted while it lives
+pub struct Transaction<'a, SPI, DC, PinError, SpiError> {
+    spi: &'a mut SPI,
+    dc: &'a mut DC,
+    _err: PhantomData<(PinError, SpiError)>,
+}
+
+impl<'a, SPI, DC, PinError, SpiError> Transaction<'a, SPI, DC, PinError, SpiError> {
+    pub(crate) fn new(spi: &'a mut SPI, dc: &'a mut DC) -> Self {
+        Transaction {
+            spi,
+            dc,
+            _err: PhantomData,
+        }
+    }
+}
+
+impl<'a, SPI, DC, PinError, SpiError> Transaction<'a, SPI, DC, PinError, SpiError>
+where
+    SPI: spi::Write<u8, Error = SpiError>,
+    DC: OutputPin<Error = PinError>,
+{
+    /// Send a command byte (DC low), a `Command` or any raw value.
+    pub fn cmd<C: Into<u8>>(&mut self, cmd: C) -> Result<&mut Self, Error<PinError, SpiError>> {
+        self.dc.set_low().map_err(Error::Pin)?;
+        self.spi.write(&[cmd.into()]).map_err(Error::Spi)?;
+
+        Ok(self)
+    }
+
+    /// Send data bytes (DC high).
+    pub fn data(&mut self, data: &[u8]) -> Result<&mut Self, Error<PinError, SpiError>> {
+        self.dc.set_high().map_err(Error::Pin)?;
+        self.spi.write(data).map_err(Error::Spi)?;
+
+        Ok(self)
+    }
+}
+
+impl<SPI, CS, DC, RST, PinError, SpiError> ST7789V<SPI, CS, DC, RST, PinError, SpiError>
+where
+    SPI: spi::Write<u8, Error = SpiError>,
+    CS: OutputPin<Error = PinError>,
+    DC: OutputPin<Error = PinError>,
+    RST: OutputPin<Error = PinError>,
+{
+    /// Assert CS once, let `f` send any mix of commands and data through the handle, then
+    /// raise CS again (also when `f` fails).
+    ///
+    /// Nothing is tracked by the driver, so registers the driver remembers (MADCTL, COLMOD,
+    /// ...) are not updated by writes made here.
+    pub fn transaction<F, R>(&mut self, f: F) -> Result<R, Error<PinError, SpiError>>
+    where
+        F: FnOnce(
+            &mut Transaction<'_, SPI, DC, PinError, SpiError>,
+        ) -> Result<R, Error<PinError, SpiError>>,
+    {
+        self.select()?;
+        let result = f(&mut Transaction::new(&mut self.spi, &mut self.cfg.dc));
+        self.deselect()?;
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::command::Command;
+    use crate::mock::{display, Event};
+
+    #[test]
+    fn cs_held_across_commands() {
+        let (mut display, _, log) = display(240, 320);
+        display
+            .transaction(|t| {
+                t.cmd(Command::COLMOD)?.data(&[0x55])?.cmd(0xB2)?.data(&[0x0C, 0x0C])?;
+                Ok(())
+            })
+            .unwrap();
+
+        let events = log.events();
+        assert_eq!(events.first(), Some(&Event::Cs(false)));
+        assert_eq!(events.last(), Some(&Event::Cs(true)));
+        assert_eq!(events.iter().filter(|e| matches!(e, Event::Cs(_))).count(), 2);
+        assert_eq!(
+            log.commands(),
+            [(Command::COLMOD.value(), vec![0x55]), (0xB2, vec![0x0C, 0x0C])]
+        );
+    }
+}
@@ -0,0 +1,212 @@
+//! Driver owning its delay, for programs that would rather not pass one to every call.
+//!
+//! `ST7789V::with_delay` wraps the driver together with a delay. The wrapper derefs to
+//! the driver, so drawing and register calls work as before, while `init`, `sleep_out`
+//! and the other calls that wait use the stored delay and take no argument.
+
+use core::ops::{Deref, DerefMut};
+
+use embedded_hal::blocking::delay::DelayMs;
+use embedded_hal::blocking::spi;
+use embedded_hal::digital::v2::OutputPin;
+
+use crate::{ColorFormat, Error, ST7789V};
+
+/// An `ST7789V` together with the delay it uses, see `ST7789V::with_delay`
+pub struct WithDelay<SPI, CS, DC, RST, PinError, SpiError, DELAY>
+where
+    SPI: spi::Write<u8>,
+    CS: OutputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+{
+    display: ST7789V<SPI, CS, DC, RST, PinError, SpiError>,
+    delay: DELAY,
+}
+
+impl<SPI, CS, DC, RST, PinError, SpiError> ST7789V<SPI, CS, DC, RST, PinError, SpiError>
+where
+    SPI: spi::Write<u8, Error = SpiError>,
+    CS: OutputPin<Error = PinError>,
+    DC: OutputPin<Error = PinError>,
+    RST: OutputPin<Error = PinError>,
+{
+    /// Keep `delay` with the driver so calls that wait no longer take one.
+    pub fn with_delay<DELAY>(
+        self,
+        delay: DELAY,
+    ) -> WithDelay<SPI, CS, DC, RST, PinError, SpiError, DELAY>
+    where
+        DELAY: DelayMs<u16>,
+    {
+        WithDelay {
+            display: self,
+            delay,
+        }
+    }
+}
+
+impl<SPI, CS, DC, RST, PinError, SpiError, DELAY>
+    WithDelay<SPI, CS, DC, RST, PinError, SpiError, DELAY>
+where
+    SPI: spi::Write<u8, Error = SpiError>,
+    CS: OutputPin<Error = PinError>,
+    DC: OutputPin<Error = PinError>,
+    RST: OutputPin<Error = PinError>,
+    DELAY: DelayMs<u16>,
+{
+    /// Initialize the display, see `ST7789V::init`
+    pub fn init(&mut self) -> Result<(), Error<PinError, SpiError>> {
+        self.display.init(&mut self.delay)
+    }
+
+    /// See `ST7789V::init_and_clear`
+    pub fn init_and_clear(&mut self, color: u16) -> Result<(), Error<PinError, SpiError>> {
+        self.display.init_and_clear(color, &mut self.delay)
+    }
+
+    /// See `ST7789V::init_no_reset`
+    pub fn init_no_reset(&mut self) -> Result<(), Error<PinError, SpiError>> {
+        self.display.init_no_reset(&mut self.delay)
+    }
+
+    /// See `ST7789V::reinit_registers`
+    pub fn reinit_registers(&mut self) -> Result<&mut Self, Error<PinError, SpiError>> {
+        self.display.reinit_registers(&mut self.delay)?;
+        Ok(self)
+    }
+
+    /// See `ST7789V::color_mode`
+    pub fn color_mode(
+        &mut self,
+        color_format: ColorFormat,
+    ) -> Result<&mut Self, Error<PinError, SpiError>> {
+        self.display.color_mode(color_format, &mut self.delay)?;
+        Ok(self)
+    }
+
+    /// See `ST7789V::sleep_in`
+    pub fn sleep_in(&mut self) -> Result<&mut Self, Error<PinError, SpiError>> {
+        self.display.sleep_in(&mut self.delay)?;
+        Ok(self)
+    }
+
+    /// See `ST7789V::sleep_out`
+    pub fn sleep_out(&mut self) -> Result<&mut Self, Error<PinError, SpiError>> {
+        self.display.sleep_out(&mut self.delay)?;
+        Ok(self)
+    }
+
+    /// See `ST7789V::display_on_delayed`
+    pub fn display_on_delayed(&mut self) -> Result<&mut Self, Error<PinError, SpiError>> {
+        self.display.display_on_delayed(&mut self.delay)?;
+        Ok(self)
+    }
+
+    /// See `ST7789V::display_off_delayed`
+    pub fn display_off_delayed(&mut self) -> Result<&mut Self, Error<PinError, SpiError>> {
+        self.display.display_off_delayed(&mut self.delay)?;
+        Ok(self)
+    }
+
+    /// See `ST7789V::hard_reset`
+    pub fn hard_reset(&mut self) -> Result<&mut Self, Error<PinError, SpiError>> {
+        self.display.hard_reset(&mut self.delay)?;
+        Ok(self)
+    }
+
+    /// See `ST7789V::soft_reset`
+    pub fn soft_reset(&mut self) -> Result<&mut Self, Error<PinError, SpiError>> {
+        self.display.soft_reset(&mut self.delay)?;
+        Ok(self)
+    }
+
+    /// See `ST7789V::recover`
+    pub fn recover(&mut self) -> Result<&mut Self, Error<PinError, SpiError>> {
+        self.display.recover(&mut self.delay)?;
+        Ok(self)
+    }
+
+    /// See `ST7789V::shutdown`
+    pub fn shutdown(&mut self) -> Result<&mut Self, Error<PinError, SpiError>> {
+        self.display.shutdown(&mut self.delay)?;
+        Ok(self)
+    }
+
+    /// Split into the driver and the delay again
+    pub fn into_inner(self) -> (ST7789V<SPI, CS, DC, RST, PinError, SpiError>, DELAY) {
+        (self.display, self.delay)
+    }
+}
+
+impl<SPI, CS, DC, RST, PinError, SpiError, DELAY> Deref
+    for WithDelay<SPI, CS, DC, RST, PinError, SpiError, DELAY>
+where
+    SPI: spi::Write<u8>,
+    CS: OutputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+{
+    type Target = ST7789V<SPI, CS, DC, RST, PinError, SpiError>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.display
+    }
+}
+
+impl<SPI, CS, DC, RST, PinError, SpiError, DELAY> DerefMut
+    for WithDelay<SPI, CS, DC, RST, PinError, SpiError, DELAY>
+where
+    SPI: spi::Write<u8>,
+    CS: OutputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.display
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::command::Command;
+    use crate::mock::{display, Event};
+
+    #[test]
+    fn stored_delay_is_used() {
+        let (display, delay, log) = display(240, 320);
+        let mut display = display.with_delay(delay);
+        display.sleep_out().unwrap();
+        display.display_on().unwrap();
+
+        assert_eq!(
+            log.events()
+                .into_iter()
+                .filter(|e| matches!(e, Event::Delay(_)))
+                .collect::<Vec<_>>(),
+            [Event::Delay(120)]
+        );
+        let cmds: Vec<u8> = log.commands().into_iter().map(|c| c.0).collect();
+        assert_eq!(cmds, [Command::SLPOUT.value(), Command::DISPON.value()]);
+    }
+
+    #[test]
+    fn shutdown_with_stored_delay() {
+        let (display, delay, log) = display(240, 320);
+        let mut display = display.with_delay(delay);
+        display.shutdown().unwrap();
+
+        let cmds: Vec<u8> = log.commands().into_iter().map(|c| c.0).collect();
+        assert_eq!(cmds, [Command::DISPOFF.value(), Command::SLPIN.value()]);
+        let events = log.events();
+        let slpin = events
+            .iter()
+            .position(|e| *e == Event::Write(vec![Command::SLPIN.value()]))
+            .unwrap();
+        // SLPIN needs its 5 ms before anything else, CS ends up high
+        assert_eq!(
+            events[slpin + 1..],
+            [Event::Cs(true), Event::Delay(5), Event::Cs(true)]
+        );
+    }
+}
@@ -0,0 +1,121 @@
+//! Streaming pixel writes, for renderers that produce a window bit by bit.
+//!
+//! `ST7789V::begin_pixels` sets the window, starts the memory write and hands out a
+//! `PixelWriter` that keeps CS asserted. Bytes pushed through it go straight into frame
+//! memory, CS is raised again when the writer is dropped or finished.
+
+use embedded_hal::blocking::spi;
+use embedded_hal::digital::v2::OutputPin;
+
+use crate::{Error, ST7789V};
+
+/// Open memory write, see `ST7789V::begin_pixels`
+pub struct PixelWriter<'a, SPI, CS, DC, RST, PinError, SpiError>
+where
+    SPI: spi::Write<u8, Error = SpiError>,
+    CS: OutputPin<Error = PinError>,
+    DC: OutputPin<Error = PinError>,
+    RST: OutputPin<Error = PinError>,
+{
+    display: &'a mut ST7789V<SPI, CS, DC, RST, PinError, SpiError>,
+}
+
+impl<SPI, CS, DC, RST, PinError, SpiError> ST7789V<SPI, CS, DC, RST, PinError, SpiError>
+where
+    SPI: spi::Write<u8, Error = SpiError>,
+    CS: OutputPin<Error = PinError>,
+    DC: OutputPin<Error = PinError>,
+    RST: OutputPin<Error = PinError>,
+{
+    /// Set the window `xs`..`xe`, `ys`..`ye` (ends exclusive, frame memory coordinates
+    /// like `address_window`) and start a memory write that `PixelWriter::push` feeds.
+    ///
+    /// The bytes are sent as they are, in the current color format and without rotation,
+    /// so this is the streaming counterpart of `mem_write` rather than of `pixels`.
+    #[allow(clippy::type_complexity)]
+    pub fn begin_pixels(
+        &mut self,
+        xs: u16,
+        ys: u16,
+        xe: u16,
+        ye: u16,
+    ) -> Result<PixelWriter<'_, SPI, CS, DC, RST, PinError, SpiError>, Error<PinError, SpiError>>
+    {
+        self.address_window(xs, ys, xe, ye)?;
+        self.select()?;
+        self.cfg.dc.set_high().map_err(Error::Pin)?;
+
+        Ok(PixelWriter { display: self })
+    }
+}
+
+impl<SPI, CS, DC, RST, PinError, SpiError> PixelWriter<'_, SPI, CS, DC, RST, PinError, SpiError>
+where
+    SPI: spi::Write<u8, Error = SpiError>,
+    CS: OutputPin<Error = PinError>,
+    DC: OutputPin<Error = PinError>,
+    RST: OutputPin<Error = PinError>,
+{
+    /// Send the next `bytes` of the window.
+    pub fn push(&mut self, bytes: &[u8]) -> Result<&mut Self, Error<PinError, SpiError>> {
+        self.display.data(bytes)?;
+
+        Ok(self)
+    }
+
+    /// Raise CS and end the write, reporting a pin error that dropping would ignore.
+    pub fn finish(self) -> Result<(), Error<PinError, SpiError>> {
+        let result = self.display.deselect();
+        core::mem::forget(self);
+        result
+    }
+}
+
+impl<SPI, CS, DC, RST, PinError, SpiError> Drop
+    for PixelWriter<'_, SPI, CS, DC, RST, PinError, SpiError>
+where
+    SPI: spi::Write<u8, Error = SpiError>,
+    CS: OutputPin<Error = PinError>,
+    DC: OutputPin<Error = PinError>,
+    RST: OutputPin<Error = PinError>,
+{
+    fn drop(&mut self) {
+        let _ = self.display.deselect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::command::Command;
+    use crate::mock::{display, Event};
+
+    #[test]
+    fn cs_held_until_drop() {
+        let (mut display, _, log) = display(240, 320);
+        {
+            let mut writer = display.begin_pixels(0, 0, 2, 1).unwrap();
+            writer
+                .push(&[0xF8, 0x00])
+                .unwrap()
+                .push(&[0x07, 0xE0])
+                .unwrap();
+            log.clear();
+            writer.push(&[]).unwrap();
+            assert!(!log.events().contains(&Event::Cs(true)));
+        }
+        assert_eq!(log.events().last(), Some(&Event::Cs(true)));
+
+        log.clear();
+        let mut writer = display.begin_pixels(0, 0, 1, 1).unwrap();
+        writer.push(&[0x12, 0x34]).unwrap();
+        writer.finish().unwrap();
+        // CASET, RASET and RAMWR each raise CS, finish once more, drop not again
+        let events = log.events();
+        assert_eq!(events.iter().filter(|e| **e == Event::Cs(true)).count(), 4);
+        assert_eq!(events.last(), Some(&Event::Cs(true)));
+        assert_eq!(
+            log.commands().last().unwrap(),
+            &(Command::RAMWR.value(), vec![0x12, 0x34])
+        );
+    }
+}